@@ -0,0 +1,90 @@
+// benches/distill.rs
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use json_distiller::core::{distill_json_with_options, DistillControl, DistillOptions};
+use serde_json::{json, Value};
+
+/// Large top-level array of API-style records with a handful of structure variants
+fn api_records(count: usize) -> Value {
+    let records: Vec<Value> = (0..count)
+        .map(|i| {
+            let items: Vec<Value> = (0..i % 5)
+                .map(|j| json!({ "sku": format!("s{}", j), "qty": j, "price": j as f64 * 1.1 }))
+                .collect();
+            let mut record = json!({
+                "id": i,
+                "type": "order",
+                "attributes": {
+                    "customer": { "name": format!("c{}", i), "email": format!("c{}@example.com", i) },
+                    "items": items,
+                    "flags": [true, false],
+                },
+            });
+            if i % 7 == 0 {
+                record["attributes"]["coupon"] = json!({ "code": "SAVE", "percent": 10 });
+            }
+            record
+        })
+        .collect();
+    Value::Array(records)
+}
+
+/// Records that nest lists of objects many levels deep (AST / GraphQL style)
+fn deep_records(count: usize, depth: usize) -> Value {
+    let records: Vec<Value> = (0..count)
+        .map(|i| {
+            let mut node = json!({ "leaf": i, "name": format!("n{}", i) });
+            for level in 0..depth {
+                node = json!({
+                    "level": level,
+                    "kind": if (i + level) % 3 == 0 { "branch" } else { "node" },
+                    "children": [node, { "leaf": level }],
+                });
+            }
+            node
+        })
+        .collect();
+    Value::Array(records)
+}
+
+fn bench_parallel_hashing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("top_level_array");
+    group.sample_size(10);
+
+    for count in [20_000, 100_000] {
+        let data = api_records(count);
+        let bytes = serde_json::to_vec(&data).map(|v| v.len()).unwrap_or(0);
+        group.throughput(Throughput::Bytes(bytes as u64));
+
+        for parallel in [false, true] {
+            let options = DistillOptions { parallel, ..DistillOptions::default() };
+            let label = if parallel { "parallel" } else { "sequential" };
+            group.bench_with_input(BenchmarkId::new(label, count), &data, |b, data| {
+                b.iter(|| distill_json_with_options(data.clone(), &options, &DistillControl::default()))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_deep_nesting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deep_nesting");
+    group.sample_size(10);
+
+    for depth in [10, 40] {
+        let data = deep_records(2_000, depth);
+        let bytes = serde_json::to_vec(&data).map(|v| v.len()).unwrap_or(0);
+        group.throughput(Throughput::Bytes(bytes as u64));
+
+        let options = DistillOptions { parallel: false, ..DistillOptions::default() };
+        group.bench_with_input(BenchmarkId::new("sequential", depth), &data, |b, data| {
+            b.iter(|| distill_json_with_options(data.clone(), &options, &DistillControl::default()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parallel_hashing, bench_deep_nesting);
+criterion_main!(benches);
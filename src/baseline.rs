@@ -0,0 +1,386 @@
+// src/baseline.rs - Structure baselines for detecting upstream shape changes
+
+use crate::core::{self, escape_pointer_token, DistillOptions, PrimitiveOrder};
+use crate::error::{DistillError, Result};
+use crate::structure::StructureInterner;
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Format version written to baseline files
+const BASELINE_VERSION: u32 = 1;
+
+/// Items shown per section of a check report before eliding the rest
+const REPORT_ITEMS: usize = 20;
+
+/// Structure hashes, signatures and field types of a payload, saved by `--snapshot`
+/// and compared against by `--check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    /// Hash scheme the structure hashes were computed with, e.g. "md5-8"
+    pub hash_scheme: String,
+    /// Options that change structure keys; a check must use the same ones
+    pub strict_typing: bool,
+    pub detect_tuples: bool,
+    pub typed_primitives: bool,
+    /// Key and path patterns left out of structure keys (see `IgnoreOptions`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_keys: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_paths: Vec<String>,
+    /// Structures of list items, by hash
+    pub structures: IndexMap<String, BaselineStructure>,
+    /// Types seen at every field path (`/data/[]/user/name`), sorted
+    pub fields: IndexMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineStructure {
+    pub signature: String,
+    /// Hash of the signature with dict keys sorted, to recognize key reorderings
+    pub canonical_hash: String,
+    pub count: usize,
+    pub first_path: String,
+}
+
+/// Result of checking a payload against a baseline
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    /// List item structures the baseline doesn't know: (hash, signature, first path)
+    pub unknown_structures: Vec<(String, String, String)>,
+    /// Structures that differ from a known one only in key order: (hash, signature, first path)
+    pub reordered_structures: Vec<(String, String, String)>,
+    /// Whether key reorderings fail the check (`primitive_order = preserve`)
+    pub order_sensitive: bool,
+    /// Field paths in the baseline missing from the payload (topmost missing path only)
+    pub missing_fields: Vec<String>,
+    /// Field paths whose types include ones the baseline never saw: (path, expected, found)
+    pub type_changes: Vec<(String, Vec<String>, Vec<String>)>,
+    /// Field paths the baseline doesn't have (informational; new keys also show up as
+    /// unknown structures when they are inside lists)
+    pub new_fields: Vec<String>,
+}
+
+impl CheckReport {
+    pub fn passed(&self) -> bool {
+        self.unknown_structures.is_empty()
+            && self.missing_fields.is_empty()
+            && self.type_changes.is_empty()
+            && (!self.order_sensitive || self.reordered_structures.is_empty())
+    }
+}
+
+/// Readable multi-line report, e.g. for CI logs
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn section<T>(
+            f: &mut fmt::Formatter<'_>,
+            title: &str,
+            items: &[T],
+            line: impl Fn(&T) -> String,
+        ) -> fmt::Result {
+            if items.is_empty() {
+                return Ok(());
+            }
+            writeln!(f, "{} ({}):", title, items.len())?;
+            for item in items.iter().take(REPORT_ITEMS) {
+                writeln!(f, "  {}", line(item))?;
+            }
+            if items.len() > REPORT_ITEMS {
+                writeln!(f, "  ... and {} more", items.len() - REPORT_ITEMS)?;
+            }
+            Ok(())
+        }
+
+        writeln!(f, "Structure check {}", if self.passed() { "PASSED" } else { "FAILED" })?;
+        section(f, "Unknown structures", &self.unknown_structures, |(hash, signature, path)| {
+            format!("+ {} {} (first at {})", hash, signature, path)
+        })?;
+        section(f, "Missing fields", &self.missing_fields, |path| format!("- {}", path))?;
+        section(f, "Type changes", &self.type_changes, |(path, expected, found)| {
+            format!("~ {}: expected {}, found {}", path, expected.join(" | "), found.join(" | "))
+        })?;
+        let reordered_title = if self.order_sensitive {
+            "Reordered keys"
+        } else {
+            "Reordered keys (ignored, primitive order is sorted)"
+        };
+        section(f, reordered_title, &self.reordered_structures, |(hash, signature, path)| {
+            format!("~ {} {} (first at {})", hash, signature, path)
+        })?;
+        section(f, "New fields (informational)", &self.new_fields, |path| format!("+ {}", path))
+    }
+}
+
+/// Everything a payload contributes to a baseline
+struct Observed {
+    structures: IndexMap<String, BaselineStructure>,
+    fields: IndexMap<String, IndexSet<&'static str>>,
+}
+
+/// Record the structures and field types of a payload
+pub fn snapshot(json_data: &Value, options: &DistillOptions) -> Result<Baseline> {
+    let observed = observe(json_data, options)?;
+    Ok(Baseline {
+        version: BASELINE_VERSION,
+        hash_scheme: options.hash_scheme.to_string(),
+        strict_typing: options.strict_typing,
+        detect_tuples: options.detect_tuples,
+        typed_primitives: options.typed_primitives,
+        ignore_keys: ignore_keys(options),
+        ignore_paths: ignore_paths(options),
+        structures: observed.structures,
+        fields: observed
+            .fields
+            .into_iter()
+            .map(|(path, types)| (path, sorted(types.into_iter().map(String::from))))
+            .collect(),
+    })
+}
+
+/// Compare a payload against a baseline. Fails on structures the baseline doesn't know,
+/// known field paths that disappeared, and field types the baseline never saw. Key
+/// reorderings only fail when `options.primitive_order` is `Preserve`.
+/// `options` must produce the same structure keys as the baseline (same hash scheme,
+/// `strict_typing`, `detect_tuples`, `typed_primitives` and ignored keys/paths).
+pub fn check(json_data: &Value, baseline: &Baseline, options: &DistillOptions) -> Result<CheckReport> {
+    if baseline.version != BASELINE_VERSION {
+        return Err(DistillError::InvalidInput(format!(
+            "Unsupported baseline version {} (expected {})",
+            baseline.version, BASELINE_VERSION
+        )));
+    }
+    let mismatches: Vec<String> = [
+        ("hash scheme", baseline.hash_scheme.clone(), options.hash_scheme.to_string()),
+        ("strict_typing", baseline.strict_typing.to_string(), options.strict_typing.to_string()),
+        ("detect_tuples", baseline.detect_tuples.to_string(), options.detect_tuples.to_string()),
+        ("typed_primitives", baseline.typed_primitives.to_string(), options.typed_primitives.to_string()),
+        ("ignored keys", format!("{:?}", baseline.ignore_keys), format!("{:?}", ignore_keys(options))),
+        ("ignored paths", format!("{:?}", baseline.ignore_paths), format!("{:?}", ignore_paths(options))),
+    ]
+    .into_iter()
+    .filter(|(_, recorded, current)| recorded != current)
+    .map(|(name, recorded, current)| format!("{} is {} in the baseline but {} now", name, recorded, current))
+    .collect();
+    if !mismatches.is_empty() {
+        return Err(DistillError::InvalidInput(format!(
+            "Baseline was recorded with different options ({}); re-create it or pass matching options",
+            mismatches.join(", ")
+        )));
+    }
+
+    let observed = observe(json_data, options)?;
+    let mut report = CheckReport {
+        order_sensitive: options.primitive_order == PrimitiveOrder::Preserve,
+        ..CheckReport::default()
+    };
+
+    let known_canonical: IndexSet<&str> =
+        baseline.structures.values().map(|s| s.canonical_hash.as_str()).collect();
+    for (hash, structure) in &observed.structures {
+        if baseline.structures.contains_key(hash) {
+            continue;
+        }
+        let entry = (hash.clone(), structure.signature.clone(), structure.first_path.clone());
+        if known_canonical.contains(structure.canonical_hash.as_str()) {
+            report.reordered_structures.push(entry);
+        } else {
+            report.unknown_structures.push(entry);
+        }
+    }
+
+    for (path, expected) in &baseline.fields {
+        match observed.fields.get(path) {
+            Some(found) if found.iter().any(|t| !expected.iter().any(|e| e == t)) => {
+                let found = sorted(found.iter().map(|t| t.to_string()));
+                report.type_changes.push((path.clone(), expected.clone(), found));
+            }
+            Some(_) => {}
+            // Only report the topmost missing path, not everything below it
+            None if parent_path(path).is_none_or(|parent| observed.fields.contains_key(parent)) => {
+                report.missing_fields.push(path.clone());
+            }
+            None => {}
+        }
+    }
+    report.new_fields = observed
+        .fields
+        .keys()
+        .filter(|path| !baseline.fields.contains_key(*path))
+        .filter(|path| parent_path(path).is_none_or(|parent| baseline.fields.contains_key(parent)))
+        .cloned()
+        .collect();
+
+    Ok(report)
+}
+
+fn ignore_keys(options: &DistillOptions) -> Vec<String> {
+    options.ignore.as_ref().map(|ignore| ignore.keys.clone()).unwrap_or_default()
+}
+
+fn ignore_paths(options: &DistillOptions) -> Vec<String> {
+    options.ignore.as_ref().map(|ignore| ignore.paths.clone()).unwrap_or_default()
+}
+
+fn observe(json_data: &Value, options: &DistillOptions) -> Result<Observed> {
+    options.hash_scheme.validate()?;
+    // Ignored members are no fields of the baseline either
+    let pruned;
+    let json_data = match &options.ignore {
+        Some(ignore) => {
+            pruned = ignore.pruned(json_data);
+            &pruned
+        }
+        None => json_data,
+    };
+    let interner = StructureInterner::new(options.hash_scheme);
+    let mut observed = Observed { structures: IndexMap::new(), fields: IndexMap::new() };
+    let mut pointer = String::new();
+    let mut field_path = String::new();
+    observe_value(json_data, options, &interner, &mut pointer, &mut field_path, &mut observed)?;
+    Ok(observed)
+}
+
+/// Walk a value, recording the type at every field path and the structure of every item
+/// of a list of objects/arrays (the structures distilled output shows `_structure_hash` for)
+fn observe_value(
+    value: &Value,
+    options: &DistillOptions,
+    interner: &StructureInterner,
+    pointer: &mut String,
+    field_path: &mut String,
+    observed: &mut Observed,
+) -> Result<()> {
+    let type_name = core::value_type_name(value, options);
+    observed.fields.entry(field_path.clone()).or_default().insert(type_name);
+
+    let pointer_len = pointer.len();
+    let path_len = field_path.len();
+    match value {
+        Value::Object(map) if type_name == "object" => {
+            for (k, v) in map {
+                pointer.push('/');
+                pointer.push_str(&escape_pointer_token(k));
+                field_path.push('/');
+                field_path.push_str(&escape_pointer_token(k));
+                observe_value(v, options, interner, pointer, field_path, observed)?;
+                pointer.truncate(pointer_len);
+                field_path.truncate(path_len);
+            }
+        }
+        Value::Array(list) => {
+            let has_containers = list.iter().any(|item| matches!(item, Value::Object(_) | Value::Array(_)));
+            let item_keys = if has_containers {
+                Some(core::item_structure_keys(list, options, interner)?)
+            } else {
+                None
+            };
+            field_path.push_str("/[]");
+            for (i, item) in list.iter().enumerate() {
+                pointer.push('/');
+                pointer.push_str(&i.to_string());
+                if let Some(keys) = &item_keys {
+                    let key = &keys[i];
+                    observed
+                        .structures
+                        .entry(key.hash().to_string())
+                        .and_modify(|s| s.count += 1)
+                        .or_insert_with(|| BaselineStructure {
+                            signature: key.signature(),
+                            canonical_hash: options.hash_scheme.digest(&key.canonical_signature()),
+                            count: 1,
+                            first_path: pointer.clone(),
+                        });
+                }
+                observe_value(item, options, interner, pointer, field_path, observed)?;
+                pointer.truncate(pointer_len);
+            }
+            field_path.truncate(path_len);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// `/data/[]/name` -> `/data/[]`; `/data` -> `` (the root); the root has no parent
+fn parent_path(path: &str) -> Option<&str> {
+    path.rfind('/').map(|i| &path[..i])
+}
+
+fn sorted(types: impl Iterator<Item = String>) -> Vec<String> {
+    let mut types: Vec<String> = types.collect();
+    types.sort_unstable();
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload() -> Value {
+        json!({ "data": [{ "id": 1, "user": { "name": "Ann" } }, { "id": 2, "user": { "name": "Bob" } }] })
+    }
+
+    #[test]
+    fn unchanged_payload_passes() {
+        let options = DistillOptions::default();
+        let baseline = snapshot(&payload(), &options).unwrap();
+        assert_eq!(baseline.structures.len(), 1);
+        assert_eq!(baseline.fields["/data/[]/user/name"], ["str"]);
+
+        let report = check(&payload(), &baseline, &options).unwrap();
+        assert!(report.passed(), "{}", report);
+    }
+
+    #[test]
+    fn new_structures_missing_fields_and_type_changes_fail() {
+        let options = DistillOptions::default();
+        let baseline = snapshot(&payload(), &options).unwrap();
+        let changed = json!({ "data": [{ "id": "1", "user": { "name": "Ann" } }, { "id": 2 }] });
+
+        let report = check(&changed, &baseline, &options).unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.unknown_structures.len(), 2);
+        assert_eq!(report.unknown_structures[0].2, "/data/0");
+        assert_eq!(report.type_changes, [("/data/[]/id".to_string(), vec!["int".to_string()], vec!["int".to_string(), "str".to_string()])]);
+        // Still present for the first item, so nothing is missing
+        assert!(report.missing_fields.is_empty());
+
+        let report = check(&json!({ "data": [{ "id": 1 }] }), &baseline, &options).unwrap();
+        // Only the topmost missing path is reported
+        assert_eq!(report.missing_fields, ["/data/[]/user"]);
+    }
+
+    #[test]
+    fn key_reorderings_only_fail_in_preserve_mode() {
+        let sorted_options = DistillOptions::default();
+        let baseline = snapshot(&payload(), &sorted_options).unwrap();
+        let reordered = json!({ "data": [{ "user": { "name": "Ann" }, "id": 1 }] });
+
+        let report = check(&reordered, &baseline, &sorted_options).unwrap();
+        assert_eq!(report.reordered_structures.len(), 1);
+        assert!(report.unknown_structures.is_empty());
+        assert!(report.passed());
+
+        let preserve = DistillOptions { primitive_order: PrimitiveOrder::Preserve, ..DistillOptions::default() };
+        assert!(!check(&reordered, &baseline, &preserve).unwrap().passed());
+    }
+
+    #[test]
+    fn checks_with_other_structure_options_are_rejected() {
+        let baseline = snapshot(&payload(), &DistillOptions::default()).unwrap();
+        let loose = DistillOptions { strict_typing: false, ..DistillOptions::default() };
+        let error = check(&payload(), &baseline, &loose).unwrap_err().to_string();
+        assert!(error.contains("strict_typing is true in the baseline but false now"), "{}", error);
+    }
+
+    #[test]
+    fn baselines_round_trip_through_json() {
+        let baseline = snapshot(&payload(), &DistillOptions::default()).unwrap();
+        let restored: Baseline = serde_json::from_str(&serde_json::to_string(&baseline).unwrap()).unwrap();
+        assert!(check(&payload(), &restored, &DistillOptions::default()).unwrap().passed());
+    }
+}
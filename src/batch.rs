@@ -0,0 +1,225 @@
+// src/batch.rs
+
+use anyhow::{bail, Context, Result};
+use json_distiller::error::DistillError;
+use json_distiller::input::InputFormat;
+use path_absolutize::Absolutize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One input file of a CLI run
+#[derive(Debug, Clone)]
+pub struct InputFile {
+    /// Absolute path of the file
+    pub path: PathBuf,
+    /// Path relative to the directory or glob it was found through, or the path as given
+    /// for explicit files. Used to name the file in reports and per-file outputs.
+    pub display_name: PathBuf,
+    /// Subdirectory of a walked directory the file was found in; per-file outputs mirror it
+    pub output_subdir: PathBuf,
+}
+
+/// Suffix of the default output file names (`batch_distilled.json`, `<stem>_distilled.json`)
+const OUTPUT_SUFFIX: &str = "_distilled.json";
+
+/// Expand the CLI input arguments into files. Directories are walked recursively for
+/// files with a known extension (see `InputFormat::from_known_extension`, hidden entries
+/// skipped); arguments that don't exist but contain `*`, `?` or `[` are glob patterns.
+/// Directory walks and globs leave out earlier outputs: files named `*_distilled.json` and
+/// the absolute paths in `excluded` (the files a run writes), so running twice on the same
+/// directory sees the same inputs. Files named explicitly are always taken.
+/// Returns the files and whether this is a batch run (several files, a directory or a glob).
+pub fn expand_inputs(arguments: &[PathBuf], excluded: &[PathBuf]) -> Result<(Vec<InputFile>, bool)> {
+    let is_output = |path: &Path| {
+        path.file_name().is_some_and(|name| name.to_string_lossy().ends_with(OUTPUT_SUFFIX))
+            || excluded.iter().any(|excluded| excluded == path)
+    };
+    let mut files = Vec::new();
+    let mut batch = arguments.len() > 1;
+
+    for argument in arguments {
+        if argument.is_dir() {
+            batch = true;
+            let mut found = Vec::new();
+            walk_directory(argument, &mut found)
+                .with_context(|| format!("Failed to read directory: {}", argument.display()))?;
+            found.sort();
+            for path in found {
+                let display_name = path.strip_prefix(argument).unwrap_or(&path).to_path_buf();
+                let output_subdir = display_name.parent().map(Path::to_path_buf).unwrap_or_default();
+                let path = absolute(&path)?;
+                if !is_output(&path) {
+                    files.push(InputFile { path, display_name, output_subdir });
+                }
+            }
+        } else if argument.is_file() {
+            files.push(InputFile {
+                path: absolute(argument)?,
+                display_name: argument.clone(),
+                output_subdir: PathBuf::new(),
+            });
+        } else if is_glob_pattern(argument) {
+            batch = true;
+            let pattern = argument.to_string_lossy();
+            let mut found: Vec<PathBuf> = glob::glob(&pattern)
+                .map_err(|e| DistillError::InvalidInput(format!("Invalid glob pattern '{}': {}", pattern, e)))?
+                .filter_map(|entry| entry.ok())
+                .filter(|path| path.is_file())
+                .collect();
+            if found.is_empty() {
+                bail!(DistillError::InvalidInput(format!("No files match '{}'", pattern)));
+            }
+            found.sort();
+            for display_name in found {
+                let path = absolute(&display_name)?;
+                if !is_output(&path) {
+                    files.push(InputFile { path, display_name, output_subdir: PathBuf::new() });
+                }
+            }
+        } else if argument.exists() {
+            bail!(DistillError::InvalidInput(format!(
+                "Input path is not a file: '{}'",
+                argument.display()
+            )));
+        } else {
+            bail!(DistillError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Input file not found at '{}'", argument.display()),
+            )));
+        }
+    }
+
+    if files.is_empty() {
+        bail!(DistillError::InvalidInput("No input files found".to_string()));
+    }
+    Ok((files, batch))
+}
+
+fn walk_directory(dir: &Path, found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk_directory(&path, found)?;
+        } else if InputFormat::from_known_extension(&path).is_some() {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_glob_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    Ok(path
+        .absolutize()
+        .context("Failed to make input path absolute")?
+        .into_owned())
+}
+
+/// File name of a path without a compression extension (`events.ndjson.gz` -> `events.ndjson`)
+pub fn uncompressed_file_name(path: &Path) -> &str {
+    let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or("output");
+    match file_name.rsplit_once('.') {
+        Some((stem, extension))
+            if ["gz", "gzip", "zst", "zstd", "bz2"].contains(&extension.to_ascii_lowercase().as_str()) =>
+        {
+            stem
+        }
+        _ => file_name,
+    }
+}
+
+/// Default output file name for an input: `<stem>_distilled.json`, with any
+/// compression extension removed first (`events.ndjson.gz` -> `events_distilled.json`)
+pub fn distilled_file_name(path: &Path) -> String {
+    let stem = Path::new(uncompressed_file_name(path))
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    format!("{}_distilled.json", stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh directory holding `files` (relative paths, created with their parents)
+    fn temp_tree(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("json-distiller-batch-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "{}").unwrap();
+        }
+        dir
+    }
+
+    fn display_names(files: &[InputFile]) -> Vec<String> {
+        files.iter().map(|file| file.display_name.to_string_lossy().replace('\\', "/")).collect()
+    }
+
+    #[test]
+    fn directories_are_walked_for_known_extensions() {
+        let dir = temp_tree(
+            "walk",
+            &["b.json", "a.yaml", "notes.txt", ".hidden.json", ".git/config.json", "sub/c.ndjson.gz", "sub/d.bin"],
+        );
+        let (files, batch) = expand_inputs(std::slice::from_ref(&dir), &[]).unwrap();
+        assert!(batch);
+        assert_eq!(display_names(&files), ["a.yaml", "b.json", "sub/c.ndjson.gz"]);
+        assert_eq!(files[2].output_subdir, Path::new("sub"));
+        assert!(files.iter().all(|file| file.path.is_absolute()));
+    }
+
+    #[test]
+    fn earlier_outputs_are_not_inputs() {
+        let dir = temp_tree("outputs", &["a.json", "batch_distilled.json", "a_distilled.json", "report.json"]);
+        let report = dir.join("report.json");
+        let (files, _) = expand_inputs(std::slice::from_ref(&dir), std::slice::from_ref(&report)).unwrap();
+        assert_eq!(display_names(&files), ["a.json"]);
+
+        let pattern = dir.join("*.json");
+        let (files, batch) = expand_inputs(&[pattern], &[report]).unwrap();
+        assert!(batch);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].path.ends_with("a.json"));
+
+        // Named explicitly, an output is still read
+        let (files, batch) = expand_inputs(&[dir.join("a_distilled.json")], &[]).unwrap();
+        assert!(!batch);
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn globs_and_missing_inputs() {
+        let dir = temp_tree("glob", &["x1.json", "x2.json", "y.json"]);
+        let (files, batch) = expand_inputs(&[dir.join("x?.json")], &[]).unwrap();
+        assert!(batch);
+        assert_eq!(files.len(), 2);
+        assert!(files[0].path.ends_with("x1.json") && files[1].path.ends_with("x2.json"));
+
+        let no_match = expand_inputs(&[dir.join("z*.json")], &[]).unwrap_err();
+        assert!(no_match.to_string().contains("No files match"), "{}", no_match);
+        let missing = expand_inputs(&[dir.join("missing.json")], &[]).unwrap_err();
+        assert!(missing.to_string().contains("not found"), "{}", missing);
+        assert!(expand_inputs(&[temp_tree("empty", &[])], &[]).is_err());
+    }
+
+    #[test]
+    fn output_names_drop_compression_extensions() {
+        assert_eq!(uncompressed_file_name(Path::new("logs/events.ndjson.gz")), "events.ndjson");
+        assert_eq!(uncompressed_file_name(Path::new("dump.bson.ZST")), "dump.bson");
+        assert_eq!(uncompressed_file_name(Path::new("data.json")), "data.json");
+        assert_eq!(distilled_file_name(Path::new("logs/events.ndjson.gz")), "events_distilled.json");
+        assert_eq!(distilled_file_name(Path::new("data.json")), "data_distilled.json");
+        assert_eq!(distilled_file_name(Path::new("README")), "README_distilled.json");
+    }
+}
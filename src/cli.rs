@@ -1,20 +1,112 @@
 // src/cli.rs
 
 use clap::Parser;
+use json_distiller::core::{HashAlgorithm, PrimitiveOrder};
+use json_distiller::input::InputFormat;
+use json_distiller::redact::{Detector, RedactStrategy};
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Distills large JSON files by summarizing repetitive list structures.", long_about = None)]
 pub struct CliArgs {
-    #[arg(index = 1)]
-    pub input_file_pos: Option<PathBuf>,
+    /// Input files, directories (searched recursively for known extensions) or glob
+    /// patterns. Several inputs are distilled together as one collection.
+    #[arg(value_name = "INPUT")]
+    pub input_file_pos: Vec<PathBuf>,
 
-    #[arg(short, long = "input", value_name = "FILE", conflicts_with = "input_file_pos")]
-    pub input_file_flag: Option<PathBuf>,
+    #[arg(short, long = "input", value_name = "FILE", conflicts_with = "input_file_pos", action = clap::ArgAction::Append)]
+    pub input_file_flag: Vec<PathBuf>,
 
+    /// Output file. With --per-file, the directory outputs are written to.
     #[arg(short, long, value_name = "FILE")]
     pub output_file: Option<PathBuf>,
 
+    /// Distill each input file on its own, in parallel, writing one
+    /// <name>_distilled.json per input instead of one collection report.
+    #[arg(long)]
+    pub per_file: bool,
+
+    /// HAR mode: group the requests of a browser HAR export by method and URL template
+    /// (/users/123 -> /users/{id}) and distill each endpoint's JSON bodies separately.
+    /// Defaults to on for .har files.
+    #[arg(long, value_name = "BOOL", action = clap::ArgAction::Set)]
+    pub har: Option<bool>,
+
+    /// Distill only the part of each input matched by SELECTOR: a JSON Pointer
+    /// (/data/0/attributes) or a JSONPath subset ($.data[*].attributes, $..author,
+    /// $.items[0:10], $['a','b']). Selectors that can match several nodes distill the
+    /// matches as one list.
+    #[arg(long, value_name = "SELECTOR")]
+    pub select: Option<String>,
+
+    /// Also write an OpenAPI 3.1 document describing the input to FILE.
+    /// Operations come from HAR input, or from --openapi-path.
+    #[arg(long, value_name = "FILE", conflicts_with = "per_file")]
+    pub openapi: Option<PathBuf>,
+
+    /// Operation the input documents are response samples of, e.g. "GET /users/{id}"
+    /// (for non-HAR input; default "GET /").
+    #[arg(long, value_name = "OPERATION", requires = "openapi")]
+    pub openapi_path: Option<String>,
+
+    /// Save the structure hashes, signatures and field types of the input to a baseline
+    /// FILE for later --check runs, instead of writing distilled output.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["check", "per_file", "openapi"])]
+    pub snapshot: Option<PathBuf>,
+
+    /// Compare the input against a baseline FILE saved with --snapshot. Prints a report and
+    /// exits with status 1 when unknown structures appear, known fields disappear or field
+    /// types change. Use the same --strict-typing/--detect-tuples as the snapshot;
+    /// --primitive-order preserve also fails on key reorderings.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["per_file", "openapi"])]
+    pub check: Option<PathBuf>,
+
+    /// Temporal mode: track when each structure hash first and last appeared, with counts
+    /// per time bucket, using the timestamp at PATH in every event (JSON Pointer such as
+    /// /meta/ts, or dotted such as meta.ts). The input must be a list of events, e.g. NDJSON.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["snapshot", "check"])]
+    pub timeline: Option<String>,
+
+    /// Bucket width of --timeline counts: a number with s, m, h, d or w (e.g. 5m, 1d)
+    #[arg(long, value_name = "DURATION", default_value = "1h", requires = "timeline")]
+    pub timeline_bucket: String,
+
+    /// Keep running and re-distill whenever an input changes (files are polled, and
+    /// directories/globs re-expanded), printing only the structural delta since the last run:
+    /// new, removed and changed structure hashes. Structure caches are reused between runs.
+    #[arg(long, conflicts_with_all = ["per_file", "snapshot", "check", "openapi", "timeline"])]
+    pub watch: bool,
+
+    /// Polling interval of --watch in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 500, requires = "watch")]
+    pub watch_interval: u64,
+
+    /// Redact sensitive values in examples: values under keys such as password, token or
+    /// email, and emails, card numbers, JWTs and IP addresses anywhere. Structures are unchanged.
+    #[arg(long)]
+    pub redact: bool,
+
+    /// Comma-separated key-name patterns whose values are redacted (matched as substrings,
+    /// ignoring case, _ and -; ^ and $ anchor to the start and end), replacing the defaults
+    #[arg(long, value_name = "PATTERNS", value_delimiter = ',', requires = "redact")]
+    pub redact_keys: Option<Vec<String>>,
+
+    /// Comma-separated value detectors: email, card, jwt, ip (default: all)
+    #[arg(long, value_name = "DETECTORS", value_delimiter = ',', requires = "redact")]
+    pub redact_detectors: Option<Vec<Detector>>,
+
+    /// Replacement for redacted values: placeholder (<email>), hash (<email:1f3a9c2b>,
+    /// equal values stay equal) or fake (same-shape fake values)
+    #[arg(long, value_name = "STRATEGY", default_value = "placeholder", requires = "redact")]
+    pub redact_strategy: RedactStrategy,
+
+    /// Input format: json, ndjson, yaml, toml, json5 or jsonc.
+    /// Defaults to the input file extension (.yaml/.yml, .toml, .json5, .jsonc, .ndjson/.jsonl),
+    /// falling back to json.
+    #[arg(long, value_name = "FORMAT")]
+    pub input_format: Option<InputFormat>,
+
     /// Enable strict type checking (int vs float are different structures).
     /// When true: treats integers and floats as distinct structure types.
     /// When false: treats all numbers as the same type.
@@ -32,22 +124,107 @@ pub struct CliArgs {
     #[arg(short, long, value_name = "N", default_value_t = 1)]
     pub repeat_threshold: usize,
 
+    /// Hash large lists in parallel across CPU cores. Output is identical either way.
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pub parallel: bool,
+
+    /// Number of worker threads for parallel hashing (default: one per CPU core).
+    #[arg(long, value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// Structure hash algorithm: md5 (Python-compatible) or blake3.
+    #[arg(long, value_name = "ALGORITHM", default_value = "md5")]
+    pub hash_algorithm: HashAlgorithm,
+
+    /// Keep at most N unique values from each list of primitives, then a summary object
+    /// (total/distinct/null counts, type mix, number range, string length range).
+    /// By default every unique value is kept.
+    #[arg(long, value_name = "N")]
+    pub max_primitive_samples: Option<usize>,
+
+    /// Cut example strings longer than N characters, ending them with "…(+123 chars)".
+    /// Keeps huge base64 blobs or HTML bodies from dominating the output.
+    #[arg(long, value_name = "N")]
+    pub max_string_len: Option<usize>,
+
+    /// With --max-string-len, report the length distribution of every field that had
+    /// strings cut (in a "string_lengths" section)
+    #[arg(long, requires = "max_string_len")]
+    pub string_length_stats: bool,
+
+    /// Replace objects and arrays nested deeper than N levels (object members and list
+    /// items each count as one) with stubs: structure hash, key/item count and a one-level
+    /// signature. Stub hashes match the rest of the output, so they can be expanded later.
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Comma-separated key names left out of structure detection at any depth (`*` is a
+    /// wildcard), for fields that vary for uninteresting reasons: _debug,extensions,x-*
+    #[arg(long, value_name = "PATTERNS", value_delimiter = ',')]
+    pub ignore_key: Vec<String>,
+
+    /// Comma-separated dotted key paths left out of structure detection, e.g.
+    /// metadata.trace. A path may start at any object but doesn't cross lists.
+    #[arg(long, value_name = "PATHS", value_delimiter = ',')]
+    pub ignore_path: Vec<String>,
+
+    /// Also remove --ignore-key/--ignore-path members from the examples
+    #[arg(long)]
+    pub ignore_in_examples: bool,
+
+    /// How lists of primitives are shown.
+    /// sorted: unique values sorted by string form (Python-compatible).
+    /// preserve: original order; arrays of a list that share a length and per-position types
+    /// (tuples such as [lat, lon]) are kept intact, other lists keep their first
+    /// --max-primitive-samples (default 10) elements.
+    #[arg(long, value_name = "ORDER", default_value = "sorted")]
+    pub primitive_order: PrimitiveOrder,
+
+    /// Detect positional tuples: arrays in a list that share a fixed length and the same
+    /// structure at each position (coordinates, chart points, CSV-like rows) get a tuple
+    /// structure such as [float, float]. Changes structure hashes, so off by default.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set)]
+    pub detect_tuples: bool,
+
+    /// Keep types that JSON can't express (ObjectId, datetime, binary, int32/int64...)
+    /// as distinct primitives, written as single-key objects like {"$oid": "..."}.
+    /// Defaults to on for the binary formats (msgpack, cbor, bson) and off otherwise;
+    /// on JSON input it recognizes MongoDB Extended JSON wrappers.
+    #[arg(long, value_name = "BOOL", action = clap::ArgAction::Set)]
+    pub typed_primitives: Option<bool>,
+
+    /// Number of hex characters kept from the structure hash digest.
+    /// 8 matches the Python implementation; use 16+ on large, heterogeneous inputs
+    /// where distinct structures may collide.
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub hash_length: usize,
+
     #[arg(long = "mcp-server",
           conflicts_with_all = ["input_file_pos", "input_file_flag", "output_file"]
     )]
     pub mcp_mode: bool,
 
+    /// Serve MCP over streamable HTTP on this address (e.g. 127.0.0.1:8808) instead of stdio.
+    /// The endpoint is mounted at /mcp; each client session gets its own document cache.
+    #[arg(long = "mcp-bind", value_name = "ADDR", requires = "mcp_mode")]
+    pub mcp_bind: Option<SocketAddr>,
+
+    /// Maximum request body size in bytes for the HTTP transport.
+    /// Larger requests are rejected with 413 Payload Too Large.
+    #[arg(long = "mcp-max-body-bytes", value_name = "BYTES", default_value_t = 64 * 1024 * 1024, requires = "mcp_bind")]
+    pub mcp_max_body_bytes: usize,
+
     #[arg(last = true, hide = true)]
     pub mcp_args: Vec<String>,
 }
 
 impl CliArgs {
-    pub fn get_input_path(&self) -> Result<PathBuf, &'static str> {
-        match (&self.input_file_pos, &self.input_file_flag) {
-            (Some(pos), None) => Ok(pos.clone()),
-            (None, Some(flag)) => Ok(flag.clone()),
-            (None, None) => Err("Input file path is required for CLI mode."),
-            (Some(_), Some(_)) => Err("Specify input file either positionally or with -i, not both."),
+    pub fn get_input_paths(&self) -> Result<Vec<PathBuf>, &'static str> {
+        match (self.input_file_pos.is_empty(), self.input_file_flag.is_empty()) {
+            (false, true) => Ok(self.input_file_pos.clone()),
+            (true, false) => Ok(self.input_file_flag.clone()),
+            (true, true) => Err("Input file path is required for CLI mode."),
+            (false, false) => Err("Specify input files either positionally or with -i, not both."),
         }
     }
 }
\ No newline at end of file
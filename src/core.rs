@@ -1,219 +1,563 @@
 // src/core.rs - HIGHLY OPTIMIZED VERSION
 
 use crate::error::{DistillError, Result};
-use ahash::AHasher;
+use crate::ignore::{IgnoreOptions, PathTail};
+use crate::input::typed_primitive_name;
+use crate::redact::RedactionOptions;
+use crate::structure::{DeepStructureKey, StructureInterner, StructureKey};
+pub use crate::structure::{HashAlgorithm, HashScheme};
+use rayon::prelude::*;
 use rustc_hash::{FxHashMap, FxHashSet};
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use serde_json::{json, Map, Value};
-use std::hash::{Hash, Hasher};
-use md5::{Md5, Digest};
-
-// Optimized: Use Vec instead of SmallVec for recursive types (avoids cycle)
-// Pre-allocate with capacity to minimize allocations
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum DeepStructureKey {
-    Primitive(&'static str),  // Zero-allocation for common type names
-    Dict(Vec<(String, DeepStructureKey)>),  // Sorted vec
-    List(Vec<DeepStructureKey>),            // Sorted vec of unique structures
-    EmptyList,
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// How lists of primitives are shown in the distilled output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrimitiveOrder {
+    /// Unique values sorted by their string form, nulls last (matches Python)
+    #[default]
+    Sorted,
+    /// Original order; positional tuples are kept intact, other lists are cut to the first N
+    Preserve,
 }
 
-// Implement Ord to match Python's tuple comparison behavior
-// Python sorts by repr() when types can't be compared directly
-impl Ord for DeepStructureKey {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.to_python_repr().cmp(&other.to_python_repr())
+impl std::fmt::Display for PrimitiveOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrimitiveOrder::Sorted => f.write_str("sorted"),
+            PrimitiveOrder::Preserve => f.write_str("preserve"),
+        }
     }
 }
 
-impl PartialOrd for DeepStructureKey {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl std::str::FromStr for PrimitiveOrder {
+    type Err = DistillError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "sorted" => Ok(PrimitiveOrder::Sorted),
+            "preserve" => Ok(PrimitiveOrder::Preserve),
+            other => Err(DistillError::InvalidInput(format!(
+                "Unknown primitive order '{}' (expected sorted or preserve)",
+                other
+            ))),
+        }
     }
 }
 
-impl DeepStructureKey {
-    /// Convert to Python repr() format for MD5 hashing
-    /// This must match Python's repr() exactly for hash compatibility
-    fn to_python_repr(&self) -> String {
-        match self {
-            DeepStructureKey::Primitive(type_name) => {
-                format!("('primitive', '{}')", type_name)
-            }
-            DeepStructureKey::EmptyList => {
-                "('list', 'empty')".to_string()
-            }
-            DeepStructureKey::List(elements) => {
-                // Elements are already sorted when DeepStructureKey::List is created
-                // Don't sort again here - that would use string comparison instead of tuple comparison
-                let element_reprs: Vec<String> = elements
-                    .iter()
-                    .map(|e| e.to_python_repr())
-                    .collect();
-                // Python requires trailing comma for single-element tuples: (x,) not (x)
-                if element_reprs.len() == 1 {
-                    format!("('list', ({},))", element_reprs[0])
-                } else {
-                    format!("('list', ({}))", element_reprs.join(", "))
-                }
-            }
-            DeepStructureKey::Dict(items) => {
-                let items_repr: Vec<String> = items
-                    .iter()
-                    .map(|(k, v)| format!("('{}', {})", k, v.to_python_repr()))
-                    .collect();
-                // Python requires trailing comma for single-element tuples: (x,) not (x)
-                if items_repr.len() == 1 {
-                    format!("('dict', ({},))", items_repr[0])
-                } else {
-                    format!("('dict', ({}))", items_repr.join(", "))
-                }
-            }
+/// Options controlling a distillation run
+#[derive(Debug, Clone)]
+pub struct DistillOptions {
+    /// Treat int/float (and other primitive types) as distinct structures
+    pub strict_typing: bool,
+    /// Minimum repeat count for pattern summarization (internal, affects formatting)
+    pub repeat_threshold: usize,
+    /// Show examples at every nesting level instead of only at the shallowest occurrence
+    pub position_dependent: bool,
+    /// Hash large lists on the rayon thread pool (output is identical to sequential mode)
+    pub parallel: bool,
+    /// Algorithm and length of structure hashes (default MD5-8, Python-compatible)
+    pub hash_scheme: HashScheme,
+    /// Keep at most this many unique values from a list of primitives, followed by a
+    /// summary object (counts, type mix, ranges). `None` keeps every unique value.
+    pub max_primitive_samples: Option<usize>,
+    /// Sort and deduplicate primitive lists (Python-compatible), or keep their order
+    pub primitive_order: PrimitiveOrder,
+    /// Give arrays of a list a positional Tuple structure when they share a fixed length
+    /// and per-position structures. Changes hashes, so off by default (Python-compatible)
+    pub detect_tuples: bool,
+    /// Treat single-key wrapper objects such as `{"$oid": ...}` or `{"$int32": ...}` as
+    /// primitives of that type (ObjectId, int32, ...), as produced by the binary input
+    /// formats. Under `strict_typing = false` they collapse to "value" like other primitives
+    pub typed_primitives: bool,
+    /// Cut example strings longer than this many characters, ending them with
+    /// `…(+N chars)`. Doesn't affect structures; `None` keeps strings whole
+    pub max_string_len: Option<usize>,
+    /// Replace objects and arrays nested more than this many levels below the root (object
+    /// members and list items each count as a level) with stubs holding their structure hash, size
+    /// and top-level signature. `None` distills the whole depth
+    pub max_depth: Option<usize>,
+    /// With `max_string_len`, add a "string_lengths" section with the length distribution
+    /// of every field that had strings cut
+    pub string_length_stats: bool,
+    /// Mask sensitive values (by key name or detected content) in examples.
+    /// Doesn't affect structures; `None` shows values as they are
+    pub redaction: Option<RedactionOptions>,
+    /// Object members left out of structure keys (and, if requested, out of examples).
+    /// Changes hashes; `None` uses every member
+    pub ignore: Option<IgnoreOptions>,
+}
+
+impl Default for DistillOptions {
+    fn default() -> Self {
+        Self {
+            strict_typing: true,
+            repeat_threshold: 1,
+            position_dependent: false,
+            parallel: true,
+            hash_scheme: HashScheme::default(),
+            max_primitive_samples: None,
+            primitive_order: PrimitiveOrder::default(),
+            detect_tuples: false,
+            typed_primitives: false,
+            redaction: None,
+            max_string_len: None,
+            string_length_stats: false,
+            max_depth: None,
+            ignore: None,
         }
     }
 }
 
-// Use IndexMap for insertion-order preservation (matches Python dict behavior)
-type StructureCache = FxHashMap<u64, DeepStructureKey>;  // Order doesn't matter for this cache
-// MemoCache key format matches Python: (is_signature, hash, depth, example_index)
-type MemoCache = IndexMap<(bool, String, usize, usize), Value>;  // Preserve order
-
-/// Hash a JSON Value directly without serialization (10-50x faster than serde+md5)
-#[inline]
-fn hash_json_value(value: &Value, strict_typing: bool) -> u64 {
-    let mut hasher = AHasher::default();
-    strict_typing.hash(&mut hasher);
-    hash_value_recursive(value, &mut hasher);
-    hasher.finish()
+/// Progress reporting and cancellation shared between a running distillation and its caller.
+/// Clones share the same state, so one clone can be moved to a worker thread while the
+/// caller polls progress or cancels through another.
+#[derive(Debug, Clone, Default)]
+pub struct DistillControl {
+    cancelled: Arc<AtomicBool>,
+    items_hashed: Arc<AtomicUsize>,
 }
 
-/// Recursively hash a JSON Value (inlined for performance)
-#[inline]
-fn hash_value_recursive(value: &Value, hasher: &mut AHasher) {
-    match value {
-        Value::Null => hasher.write_u8(0),
-        Value::Bool(b) => {
-            hasher.write_u8(1);
-            b.hash(hasher);
-        }
-        Value::Number(n) => {
-            hasher.write_u8(2);
-            n.to_string().hash(hasher);
+impl DistillControl {
+    /// Request cancellation; the distillation stops at the next list item with `DistillError::Cancelled`
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Number of list items hashed so far (across all passes)
+    pub fn items_hashed(&self) -> usize {
+        self.items_hashed.load(Ordering::Relaxed)
+    }
+
+    /// Record one hashed list item, failing if cancellation was requested
+    #[inline]
+    fn tick(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(DistillError::Cancelled);
         }
-        Value::String(s) => {
-            hasher.write_u8(3);
-            s.hash(hasher);
+        self.items_hashed.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Structure keys kept across distillation runs, e.g. while watching a file that is
+/// re-distilled on every change: structures seen in an earlier run are not hashed again.
+/// A cache only serves options with the hash scheme it was created for. Hash collisions
+/// are reported for every structure the cache has seen, not just the current document's.
+pub struct StructureCache {
+    interner: StructureInterner,
+}
+
+impl StructureCache {
+    pub fn new(scheme: HashScheme) -> Self {
+        Self { interner: StructureInterner::new(scheme) }
+    }
+
+    /// Number of distinct structures cached so far
+    pub fn len(&self) -> usize {
+        self.interner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn interner_for(&self, options: &DistillOptions) -> Result<&StructureInterner> {
+        options.hash_scheme.validate()?;
+        if self.interner.scheme() != options.hash_scheme {
+            return Err(DistillError::InvalidInput(format!(
+                "Structure cache was created for hash scheme {}, not {}",
+                self.interner.scheme(),
+                options.hash_scheme
+            )));
         }
-        Value::Array(arr) => {
-            hasher.write_u8(4);
-            hasher.write_usize(arr.len());
-            for item in arr {
-                hash_value_recursive(item, hasher);
+        Ok(&self.interner)
+    }
+}
+
+// MemoCache key format matches Python: (is_signature, hash, depth, example_index).
+// Values carry the number of redactions inside them, so reused examples are counted again
+type MemoCache = IndexMap<(bool, String, usize, usize), (Value, usize)>;  // Preserve order
+
+/// Minimum list length before items are hashed on the rayon thread pool.
+/// Below this, thread coordination costs more than it saves.
+const PARALLEL_MIN_ITEMS: usize = 512;
+
+/// Elements kept from a primitive list in preserve mode when `max_primitive_samples` isn't set
+const PRESERVED_PRIMITIVES: usize = 10;
+
+/// Longest array that tuple detection treats as positional (CSV-like rows included)
+const MAX_TUPLE_ARITY: usize = 16;
+
+/// Keys or element types listed in the signature of a depth-limit stub
+const STUB_SIGNATURE_ENTRIES: usize = 8;
+
+/// Per-run settings shared by the recursive passes
+struct DistillContext<'a> {
+    options: &'a DistillOptions,
+    control: &'a DistillControl,
+    interner: &'a StructureInterner,
+    /// Values redacted so far (see `DistillOptions::redaction`)
+    redactions: AtomicUsize,
+    /// Signatures of the positional tuples shown intact, e.g. `[float, float]`
+    tuples: Mutex<IndexSet<String>>,
+}
+
+impl DistillContext<'_> {
+    /// Redacted copy of a primitive (see `RedactionOptions::redact`)
+    fn redact(&self, value: &Value, sensitive: bool) -> Value {
+        match self.options.redaction.as_ref().and_then(|r| r.redact(value, sensitive)) {
+            Some(redacted) => {
+                self.redactions.fetch_add(1, Ordering::Relaxed);
+                redacted
             }
+            None => value.clone(),
         }
-        Value::Object(obj) => {
-            hasher.write_u8(5);
-            hasher.write_usize(obj.len());
-            // Collect and sort keys for deterministic hashing
-            let mut keys: Vec<&String> = obj.keys().collect();
-            keys.sort_unstable();
-            for key in keys {
-                key.hash(hasher);
-                hash_value_recursive(&obj[key], hasher);
+    }
+
+    /// Whether containers at `depth` are replaced by stubs (see `DistillOptions::max_depth`)
+    fn is_stubbed(&self, depth: usize) -> bool {
+        self.options.max_depth.is_some_and(|max_depth| depth > max_depth)
+    }
+
+    /// Stub standing in for a subtree below `max_depth`, or `None` for primitives
+    /// (typed primitives included) and empty containers, which are shown as they are.
+    /// `key` is the value's structure key, as its list computed it for list items
+    fn stub(&self, value: &Value, key: &StructureKey) -> Option<Value> {
+        let (kind, size_name, size) = match value {
+            Value::Object(map) if !map.is_empty() && value_type_name(value, self.options) == "object" => {
+                ("object", "keys", map.len())
             }
+            Value::Array(list) if !list.is_empty() => ("array", "items", list.len()),
+            _ => return None,
+        };
+        let mut stub = Map::new();
+        stub.insert("_stub".to_string(), json!(kind));
+        stub.insert("_structure_hash".to_string(), json!(key.hash()));
+        stub.insert(size_name.to_string(), json!(size));
+        stub.insert("signature".to_string(), json!(key.short_signature(STUB_SIGNATURE_ENTRIES)));
+        Some(Value::Object(stub))
+    }
+
+    /// A primitive as shown in an example: redacted, then cut to `max_string_len`
+    fn emit(&self, value: &Value, sensitive: bool) -> Value {
+        let value = self.redact(value, sensitive);
+        match (&value, self.options.max_string_len) {
+            (Value::String(s), Some(max_len)) => truncate_string(s, max_len).map(Value::String).unwrap_or(value),
+            _ => value,
         }
     }
-}
 
-#[inline]
-fn get_deep_structure_key_cached(
-    item: &Value,
-    strict_typing: bool,
-    cache: &mut StructureCache,
-) -> Result<DeepStructureKey> {
-    // Optimization: Skip caching for primitives (faster to recompute than cache lookup)
-    if !matches!(item, Value::Object(_) | Value::Array(_)) {
-        return get_deep_structure_key_impl(item, strict_typing, cache);
+    /// Signature of the positional tuples that make up `list`, if its items are tuple rows
+    /// of primitives. Under `detect_tuples` that is whatever the list resolved to a Tuple
+    /// key; otherwise only preserve mode looks for tuples (see `tuple_positions`)
+    fn tuple_signature(&self, list: &[Value], item_keys: &[StructureKey]) -> Option<String> {
+        if self.options.detect_tuples {
+            let key = item_keys.first()?;
+            let primitive_rows = list.iter().all(|row| {
+                matches!(row, Value::Array(row) if row.iter().all(|v| !matches!(v, Value::Object(_) | Value::Array(_))))
+            });
+            return (matches!(key.node(), DeepStructureKey::Tuple(_)) && primitive_rows).then(|| key.signature());
+        }
+        if self.options.primitive_order != PrimitiveOrder::Preserve {
+            return None;
+        }
+        tuple_positions(list, self.options).map(|positions| format!("[{}]", positions.join(", ")))
     }
 
-    // Optimization: Hash directly without serialization (10-50x faster)
-    let cache_key = hash_json_value(item, strict_typing);
+    /// Emit a tuple row as it is: every position kept, in order
+    fn emit_tuple(&self, row: &Value, signature: &str, sensitive: bool) -> Value {
+        let mut tuples = self.tuples.lock().unwrap_or_else(|e| e.into_inner());
+        tuples.insert(signature.to_string());
+        self.emit_primitive_list(row.clone(), sensitive)
+    }
 
-    // Check cache first (FxHashMap is 2x faster than DashMap for single-threaded)
-    if let Some(cached) = cache.get(&cache_key) {
-        return Ok(cached.clone());
+    /// Emit the values of a distilled primitive list. A trailing summary object only
+    /// holds counts, except the number range of values under a sensitive key
+    fn emit_primitive_list(&self, distilled: Value, sensitive: bool) -> Value {
+        let Value::Array(values) = distilled else {
+            return distilled;
+        };
+        Value::Array(
+            values
+                .into_iter()
+                .map(|value| match value {
+                    Value::Object(mut summary) => {
+                        if let Some(Value::Object(range)) = summary.get_mut("number_range") {
+                            for bound in range.values_mut() {
+                                *bound = self.redact(bound, sensitive);
+                            }
+                        }
+                        Value::Object(summary)
+                    }
+                    value => self.emit(&value, sensitive),
+                })
+                .collect(),
+        )
     }
+}
 
-    // Compute and cache
-    let result = get_deep_structure_key_impl(item, strict_typing, cache)?;
-    cache.insert(cache_key, result.clone());
-    Ok(result)
+/// Options that affect structure keys (and therefore hashes)
+#[derive(Debug, Clone, Copy)]
+struct KeyOptions<'a> {
+    strict_typing: bool,
+    detect_tuples: bool,
+    typed_primitives: bool,
+    ignore: Option<&'a IgnoreOptions>,
+}
+
+impl<'a> From<&'a DistillOptions> for KeyOptions<'a> {
+    fn from(options: &'a DistillOptions) -> Self {
+        Self {
+            strict_typing: options.strict_typing,
+            detect_tuples: options.detect_tuples,
+            typed_primitives: options.typed_primitives,
+            ignore: options.ignore.as_ref(),
+        }
+    }
 }
 
+/// Structure key of a value on its own, plus (for non-empty arrays, when tuple detection
+/// is on) the keys of its elements by position, so the enclosing list can decide whether
+/// its arrays are tuples
+type KeyParts = (StructureKey, Option<Vec<StructureKey>>);
+
+/// Compute the interned structure key of a value, bottom-up.
+/// The interner is the only cache: a node is looked up by its type, dict keys and
+/// (already interned) children, so values with different content but the same
+/// structure share one entry, and a hash collision falls back to a full equality check
+/// instead of returning another structure's key.
 #[inline]
-fn get_deep_structure_key_impl(
+fn get_deep_structure_key<S: BuildHasher + Clone>(
     item: &Value,
-    strict_typing: bool,
-    cache: &mut StructureCache,
-) -> Result<DeepStructureKey> {
-    match item {
+    keys: KeyOptions,
+    interner: &StructureInterner<S>,
+) -> Result<StructureKey> {
+    get_key_parts(item, keys, interner, &[]).map(|(key, _)| key)
+}
+
+/// `tails` are the ignore paths (see `IgnoreOptions::paths`) that reached `item` from
+/// enclosing objects; lists start over with none
+fn get_key_parts<'a, S: BuildHasher + Clone>(
+    item: &Value,
+    keys: KeyOptions<'a>,
+    interner: &StructureInterner<S>,
+    tails: &[PathTail<'a>],
+) -> Result<KeyParts> {
+    let strict_typing = keys.strict_typing;
+    let node = match item {
         Value::Object(map) => {
+            if keys.typed_primitives {
+                if let Some(type_name) = typed_primitive_name(map) {
+                    let type_name = if strict_typing { type_name } else { "value" };
+                    return Ok((interner.primitive(type_name), None));
+                }
+            }
             // DON'T sort! Python preserves insertion order for dicts (3.7+)
             // Sorting would produce different structure hashes
-            let mut pairs: Vec<(String, DeepStructureKey)> = Vec::with_capacity(map.len());
+            let mut pairs: Vec<(String, StructureKey)> = Vec::with_capacity(map.len());
             for (k, v) in map {
-                pairs.push((k.clone(), get_deep_structure_key_cached(v, strict_typing, cache)?));
+                let key = match keys.ignore {
+                    None => get_deep_structure_key(v, keys, interner)?,
+                    Some(ignore) => match ignore.member(tails, k) {
+                        Some(tails) => get_key_parts(v, keys, interner, &tails)?.0,
+                        None => continue, // Ignored members aren't part of the structure
+                    },
+                };
+                pairs.push((k.clone(), key));
             }
             // Note: serde_json::Map preserves insertion order, so we maintain it
-            Ok(DeepStructureKey::Dict(pairs))
+            DeepStructureKey::Dict(pairs)
         }
         Value::Array(list) => {
             if list.is_empty() {
-                Ok(DeepStructureKey::EmptyList)
+                DeepStructureKey::EmptyList
             } else {
+                let parts = list
+                    .iter()
+                    .map(|elem| get_key_parts(elem, keys, interner, &[]))
+                    .collect::<Result<Vec<_>>>()?;
+                let positional = resolve_tuples(parts, interner);
+
                 // Optimization: Use FxHashSet (2x faster than std HashSet)
                 let mut element_keys = FxHashSet::with_capacity_and_hasher(
                     list.len().min(16),  // Cap initial capacity
                     Default::default()
                 );
-                for elem in list {
-                    element_keys.insert(get_deep_structure_key_cached(elem, strict_typing, cache)?);
-                }
-                // Convert to sorted vec
-                let mut sorted_keys: Vec<DeepStructureKey> = element_keys.into_iter().collect();
+                element_keys.extend(positional.iter().cloned());
+                // Convert to sorted vec (compares cached reprs, no string building)
+                let mut sorted_keys: Vec<StructureKey> = element_keys.into_iter().collect();
                 sorted_keys.sort_unstable();
-                Ok(DeepStructureKey::List(sorted_keys))
+                let key = interner.intern(DeepStructureKey::List(sorted_keys));
+                return Ok((key, keys.detect_tuples.then_some(positional)));
             }
         }
         Value::Null => {
-            // Optimization: Use &'static str (zero allocation)
-            if strict_typing {
-                Ok(DeepStructureKey::Primitive("NoneType"))
-            } else {
-                Ok(DeepStructureKey::Primitive("value"))
-            }
+            // Primitives are pre-interned, so leaves never touch the interner map
+            let type_name = if strict_typing { "NoneType" } else { "value" };
+            return Ok((interner.primitive(type_name), None));
         }
         _ => {
-            if strict_typing {
-                // Optimization: Use &'static str for common types
-                let type_key = match item {
-                    Value::Bool(_) => DeepStructureKey::Primitive("bool"),
-                    Value::String(_) => DeepStructureKey::Primitive("str"),
+            let type_name = if strict_typing {
+                match item {
+                    Value::Bool(_) => "bool",
+                    Value::String(_) => "str",
                     Value::Number(n) => {
                         if n.is_f64() {
-                            DeepStructureKey::Primitive("float")
+                            "float"
                         } else {
-                            DeepStructureKey::Primitive("int")
+                            "int"
                         }
                     }
                     _ => return Err(DistillError::Internal("Unexpected type in primitive match arm".to_string())),
-                };
-                Ok(type_key)
+                }
             } else {
-                Ok(DeepStructureKey::Primitive("value"))
+                "value"
+            };
+            return Ok((interner.primitive(type_name), None));
+        }
+    };
+    Ok((interner.intern(node), None))
+}
+
+/// Resolve the keys of a list's items. When every item is an array of the same length
+/// (2..=MAX_TUPLE_ARITY) with the same structure at each position, the arrays are
+/// positional tuples (coordinates, chart points, CSV-like rows) and all get one Tuple key;
+/// otherwise each item keeps its own key.
+fn resolve_tuples<S: BuildHasher + Clone>(parts: Vec<KeyParts>, interner: &StructureInterner<S>) -> Vec<StructureKey> {
+    let tuple_positions = match parts.first() {
+        Some((_, Some(first))) if parts.len() >= 2 && (2..=MAX_TUPLE_ARITY).contains(&first.len()) => {
+            parts.iter().all(|(_, positions)| positions.as_ref() == Some(first)).then(|| first.clone())
+        }
+        _ => None,
+    };
+
+    match tuple_positions {
+        Some(positions) => {
+            let tuple_key = interner.intern(DeepStructureKey::Tuple(positions));
+            vec![tuple_key; parts.len()]
+        }
+        None => parts.into_iter().map(|(key, _)| key).collect(),
+    }
+}
+
+/// Structure keys of every item in a list, in list order (with tuple detection applied)
+fn list_item_keys<S: BuildHasher + Clone>(
+    list: &[Value],
+    keys: KeyOptions,
+    interner: &StructureInterner<S>,
+) -> Result<Vec<StructureKey>> {
+    let parts = list
+        .iter()
+        .map(|item| get_key_parts(item, keys, interner, &[]))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(resolve_tuples(parts, interner))
+}
+
+/// Structure key of a single value under the given options, for exporters that walk
+/// structure trees (e.g. OpenAPI schemas)
+pub(crate) fn structure_key(
+    value: &Value,
+    options: &DistillOptions,
+    interner: &StructureInterner,
+) -> Result<StructureKey> {
+    get_deep_structure_key(value, KeyOptions::from(options), interner)
+}
+
+/// Structure keys of a list's items, as the distiller computes them (tuples included)
+pub(crate) fn item_structure_keys(
+    list: &[Value],
+    options: &DistillOptions,
+    interner: &StructureInterner,
+) -> Result<Vec<StructureKey>> {
+    list_item_keys(list, KeyOptions::from(options), interner)
+}
+
+/// Call `visit` with the structure key of every item of every list of objects/arrays
+/// nested in `value` (the items distilled output annotates with `_structure_hash`)
+pub(crate) fn visit_nested_item_keys(
+    value: &Value,
+    options: &DistillOptions,
+    interner: &StructureInterner,
+    visit: &mut dyn FnMut(&StructureKey),
+) -> Result<()> {
+    match value {
+        Value::Object(map) => {
+            for v in map.values() {
+                visit_nested_item_keys(v, options, interner, visit)?;
             }
         }
+        Value::Array(list) => {
+            if list.iter().all(|item| !matches!(item, Value::Object(_) | Value::Array(_))) {
+                return Ok(());
+            }
+            let keys = item_structure_keys(list, options, interner)?;
+            for (item, key) in list.iter().zip(&keys) {
+                visit(key);
+                visit_nested_item_keys(item, options, interner, visit)?;
+            }
+        }
+        _ => {}
     }
+    Ok(())
+}
+
+/// Type of a single value as structure keys see it: a primitive type name ("value" when
+/// typing isn't strict), a typed primitive such as "ObjectId", or "object" / "array"
+pub(crate) fn value_type_name(item: &Value, options: &DistillOptions) -> &'static str {
+    let strict_typing = options.strict_typing;
+    match item {
+        Value::Object(map) => match typed_primitive_name(map).filter(|_| options.typed_primitives) {
+            Some(type_name) if strict_typing => type_name,
+            Some(_) => "value",
+            None => "object",
+        },
+        Value::Array(_) => "array",
+        _ if !strict_typing => "value",
+        Value::Null => "NoneType",
+        Value::Bool(_) => "bool",
+        Value::String(_) => "str",
+        Value::Number(n) => {
+            if n.is_f64() {
+                "float"
+            } else {
+                "int"
+            }
+        }
+    }
+}
+
+/// Compute the structure hash of every item in a list, in list order.
+/// Large lists are hashed on the rayon thread pool when parallel hashing is enabled;
+/// the resulting sequence is identical to the sequential one.
+fn hash_list_items(list: &[Value], ctx: &DistillContext) -> Result<Vec<String>> {
+    Ok(hash_list_item_keys(list, ctx)?
+        .iter()
+        .map(|key| key.hash().to_string())
+        .collect())
+}
+
+/// Structure keys behind `hash_list_items`
+fn hash_list_item_keys(list: &[Value], ctx: &DistillContext) -> Result<Vec<StructureKey>> {
+    let keys = KeyOptions::from(ctx.options);
+    let key_item = |item: &Value| -> Result<KeyParts> {
+        ctx.control.tick()?;
+        get_key_parts(item, keys, ctx.interner, &[])
+    };
+
+    let parts = if ctx.options.parallel && list.len() >= PARALLEL_MIN_ITEMS {
+        list.par_iter().map(key_item).collect::<Result<Vec<_>>>()?
+    } else {
+        list.iter().map(key_item).collect::<Result<Vec<_>>>()?
+    };
+    Ok(resolve_tuples(parts, ctx.interner))
 }
 
 /// Pass 1: Collect minimum depth for each structure hash
@@ -221,15 +565,14 @@ fn get_deep_structure_key_impl(
 fn collect_structure_depths(
     container: &Value,
     depth: usize,
-    strict_typing: bool,
-    cache: &mut StructureCache,
+    ctx: &DistillContext,
     accumulator: &mut FxHashMap<String, usize>,
 ) -> Result<()> {
     match container {
         Value::Object(map) => {
             // Recurse on all values
             for v in map.values() {
-                collect_structure_depths(v, depth + 1, strict_typing, cache, accumulator)?;
+                collect_structure_depths(v, depth + 1, ctx, accumulator)?;
             }
             Ok(())
         }
@@ -248,10 +591,8 @@ fn collect_structure_depths(
             }
 
             // Compute hashes for all items in this list
-            for item in list {
-                let deep_key = get_deep_structure_key_cached(item, strict_typing, cache)?;
-                let current_hash = generate_hash(&deep_key)?;
-
+            let hashes = hash_list_items(list, ctx)?;
+            for (item, current_hash) in list.iter().zip(hashes) {
                 // Track minimum depth for this hash
                 accumulator
                     .entry(current_hash)
@@ -259,7 +600,7 @@ fn collect_structure_depths(
                     .or_insert(depth);
 
                 // Recurse into the item to find nested structures
-                collect_structure_depths(item, depth + 1, strict_typing, cache, accumulator)?;
+                collect_structure_depths(item, depth + 1, ctx, accumulator)?;
             }
 
             Ok(())
@@ -268,19 +609,6 @@ fn collect_structure_depths(
     }
 }
 
-#[inline]
-fn generate_hash(key: &DeepStructureKey) -> Result<String> {
-    // Use MD5 to match Python's hash generation exactly
-    // Python: hashlib.md5(repr(key).encode('utf-8')).hexdigest()[:8]
-    let repr_string = key.to_python_repr();
-    let mut hasher = Md5::new();
-    hasher.update(repr_string.as_bytes());
-    let result = hasher.finalize();
-    // Take first 8 hex characters (4 bytes)
-    Ok(format!("{:02x}{:02x}{:02x}{:02x}",
-        result[0], result[1], result[2], result[3]))
-}
-
 #[inline]
 fn find_adjacent_patterns_python_style(hash_sequence: &[String]) -> Vec<Value> {
     if hash_sequence.is_empty() {
@@ -312,28 +640,27 @@ fn find_adjacent_patterns_python_style(hash_sequence: &[String]) -> Vec<Value> {
 
         // Check for alternating pattern (AB AB AB...)
         // Matches Python: requires pattern to appear at i+2:i+4
-        if i + 3 < n {
-            if hash_sequence[i + 2] == hash_sequence[i] &&
-               hash_sequence[i + 3] == hash_sequence[i + 1] {
-                let pattern_a = &hash_sequence[i];
-                let pattern_b = &hash_sequence[i + 1];
-
-                // Count how many complete pairs we have
-                // Start at 1 since we've confirmed pattern appears twice (at i:i+2 and i+2:i+4)
-                let mut run_len_pairs = 1;
-                while i + (run_len_pairs + 1) * 2 <= n &&
-                      hash_sequence.get(i + run_len_pairs * 2) == Some(pattern_a) &&
-                      hash_sequence.get(i + run_len_pairs * 2 + 1) == Some(pattern_b) {
-                    run_len_pairs += 1;
-                }
-
-                output_sequence.push(json!({
-                    "pattern": [pattern_a, pattern_b],
-                    "repeat": run_len_pairs
-                }));
-                i += run_len_pairs * 2;
-                continue;
+        if i + 3 < n &&
+           hash_sequence[i + 2] == hash_sequence[i] &&
+           hash_sequence[i + 3] == hash_sequence[i + 1] {
+            let pattern_a = &hash_sequence[i];
+            let pattern_b = &hash_sequence[i + 1];
+
+            // Count how many complete pairs we have
+            // Start at 1 since we've confirmed pattern appears twice (at i:i+2 and i+2:i+4)
+            let mut run_len_pairs = 1;
+            while i + (run_len_pairs + 1) * 2 <= n &&
+                  hash_sequence.get(i + run_len_pairs * 2) == Some(pattern_a) &&
+                  hash_sequence.get(i + run_len_pairs * 2 + 1) == Some(pattern_b) {
+                run_len_pairs += 1;
             }
+
+            output_sequence.push(json!({
+                "pattern": [pattern_a, pattern_b],
+                "repeat": run_len_pairs
+            }));
+            i += run_len_pairs * 2;
+            continue;
         }
 
         output_sequence.push(Value::String(current_hash.clone()));
@@ -373,25 +700,271 @@ fn format_pattern_to_string_python_style(pattern_output: &[Value]) -> String {
     parts.join(" ")
 }
 
+/// Distill a list of primitives according to `options.primitive_order`.
+/// Tuple rows never get here: their lists emit them whole (see `DistillContext::tuple_signature`)
+fn distill_primitive_list(list: &[Value], options: &DistillOptions) -> Value {
+    match options.primitive_order {
+        PrimitiveOrder::Sorted => distill_primitive_list_sorted(list, options.max_primitive_samples),
+        PrimitiveOrder::Preserve => distill_primitive_list_ordered(list, options.max_primitive_samples),
+    }
+}
+
+/// Position types of a list whose items are all primitive arrays of one length
+/// (2..=MAX_TUPLE_ARITY) with the same type at each position, such as `[lat, lon]`
+/// coordinates or `[timestamp, value]` points. `None` if the items aren't such tuples
+fn tuple_positions(list: &[Value], options: &DistillOptions) -> Option<Vec<&'static str>> {
+    let row_positions = |item: &Value| match item {
+        Value::Array(row) if (2..=MAX_TUPLE_ARITY).contains(&row.len()) => row
+            .iter()
+            .map(|value| match value {
+                Value::Object(_) | Value::Array(_) => None,
+                value => Some(value_type_name(value, options)),
+            })
+            .collect::<Option<Vec<_>>>(),
+        _ => None,
+    };
+    let (first, rest) = list.split_first()?;
+    let positions = row_positions(first)?;
+    (!rest.is_empty() && rest.iter().all(|item| row_positions(item).as_ref() == Some(&positions))).then_some(positions)
+}
+
+/// Ordered mode: the first `max_samples` elements in original order (duplicates and
+/// nulls in place), then a summary object. Tuple rows never get here (see `tuple_positions`)
+fn distill_primitive_list_ordered(list: &[Value], max_samples: Option<usize>) -> Value {
+    let max_samples = max_samples.unwrap_or(PRESERVED_PRIMITIVES);
+    if list.len() <= max_samples {
+        return Value::Array(list.to_vec());
+    }
+
+    let kept = &list[..max_samples];
+    let distinct_count = list.iter().filter(|v| !v.is_null()).collect::<FxHashSet<_>>().len();
+    let kept_distinct = kept.iter().filter(|v| !v.is_null()).collect::<FxHashSet<_>>().len();
+    let null_count = list.iter().filter(|v| v.is_null()).count();
+
+    let mut values = kept.to_vec();
+    values.push(summarize_primitives(list, distinct_count, distinct_count - kept_distinct, null_count));
+    Value::Array(values)
+}
+
+/// Sorted mode: the list's unique values, sorted, with nulls kept at the end.
+/// With `max_samples` set, lists with more distinct values keep only the first samples
+/// followed by a summary object, so long lists of IDs or timestamps still compress.
+fn distill_primitive_list_sorted(list: &[Value], max_samples: Option<usize>) -> Value {
+    // Collect unique values
+    let mut unique_values: FxHashSet<&Value> = FxHashSet::default();
+    for item in list {
+        unique_values.insert(item);
+    }
+
+    // Sort values (null at end)
+    let mut sorted_values: Vec<Value> = unique_values.into_iter()
+        .filter(|v| !v.is_null())
+        .cloned()
+        .collect();
+
+    // Sort using JSON string representation for consistent ordering
+    sorted_values.sort_by(|a, b| {
+        match (a, b) {
+            (Value::Number(n1), Value::Number(n2)) => {
+                n1.to_string().cmp(&n2.to_string())
+            }
+            (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
+            (Value::Bool(b1), Value::Bool(b2)) => b1.cmp(b2),
+            _ => serde_json::to_string(a).unwrap_or_default()
+                .cmp(&serde_json::to_string(b).unwrap_or_default())
+        }
+    });
+
+    let null_count = list.iter().filter(|v| v.is_null()).count();
+
+    if let Some(max_samples) = max_samples.filter(|max| sorted_values.len() > *max) {
+        let distinct_count = sorted_values.len();
+        sorted_values.truncate(max_samples);
+        sorted_values.push(summarize_primitives(list, distinct_count, distinct_count - max_samples, null_count));
+        return Value::Array(sorted_values);
+    }
+
+    // Add nulls at end
+    for _ in 0..null_count {
+        sorted_values.push(Value::Null);
+    }
+
+    Value::Array(sorted_values)
+}
+
+/// `s` cut to `max_len` characters with a `…(+N chars)` suffix, if it is longer
+fn truncate_string(s: &str, max_len: usize) -> Option<String> {
+    let (cut, _) = s.char_indices().nth(max_len)?;
+    Some(format!("{}…(+{} chars)", &s[..cut], s[cut..].chars().count()))
+}
+
+/// Upper bounds of the buckets of a string length histogram (the last one is open)
+const STRING_LENGTH_BUCKETS: [(usize, &str); 6] =
+    [(10, "<10"), (100, "<100"), (1_000, "<1k"), (10_000, "<10k"), (100_000, "<100k"), (1_000_000, "<1M")];
+
+/// Length distribution of the strings at one field path
+#[derive(Default)]
+struct StringLengths {
+    count: usize,
+    truncated: usize,
+    min: usize,
+    max: usize,
+    total: usize,
+    histogram: [usize; STRING_LENGTH_BUCKETS.len() + 1],
+}
+
+/// Record the length of every string in `value` under its field path (`/data/[]/body`)
+fn collect_string_lengths(
+    value: &Value,
+    max_len: usize,
+    field_path: &mut String,
+    stats: &mut IndexMap<String, StringLengths>,
+) {
+    let path_len = field_path.len();
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                field_path.push('/');
+                field_path.push_str(&escape_pointer_token(k));
+                collect_string_lengths(v, max_len, field_path, stats);
+                field_path.truncate(path_len);
+            }
+        }
+        Value::Array(list) => {
+            field_path.push_str("/[]");
+            for item in list {
+                collect_string_lengths(item, max_len, field_path, stats);
+            }
+            field_path.truncate(path_len);
+        }
+        Value::String(s) => {
+            let len = s.chars().count();
+            let entry = stats.entry(field_path.clone()).or_default();
+            entry.min = if entry.count == 0 { len } else { entry.min.min(len) };
+            entry.max = entry.max.max(len);
+            entry.count += 1;
+            entry.total += len;
+            if len > max_len {
+                entry.truncated += 1;
+            }
+            let bucket = STRING_LENGTH_BUCKETS
+                .iter()
+                .position(|(bound, _)| len < *bound)
+                .unwrap_or(STRING_LENGTH_BUCKETS.len());
+            entry.histogram[bucket] += 1;
+        }
+        _ => {}
+    }
+}
+
+/// "string_lengths" section: the length distribution of every field with strings over `max_len`
+fn string_length_report(json_data: &Value, max_len: usize) -> Value {
+    let mut stats = IndexMap::new();
+    collect_string_lengths(json_data, max_len, &mut String::new(), &mut stats);
+    let report: Map<String, Value> = stats
+        .into_iter()
+        .filter(|(_, lengths)| lengths.truncated > 0)
+        .map(|(path, lengths)| {
+            let labels = STRING_LENGTH_BUCKETS.iter().map(|(_, label)| *label).chain([">=1M"]);
+            let histogram: Map<String, Value> = labels
+                .zip(lengths.histogram)
+                .filter(|(_, count)| *count > 0)
+                .map(|(label, count)| (label.to_string(), json!(count)))
+                .collect();
+            let stats = json!({
+                "count": lengths.count,
+                "truncated": lengths.truncated,
+                "min": lengths.min,
+                "mean": lengths.total / lengths.count,
+                "max": lengths.max,
+                "histogram": histogram,
+            });
+            (path, stats)
+        })
+        .collect();
+    Value::Object(report)
+}
+
+/// Summary object appended after the samples of a long primitive list
+fn summarize_primitives(list: &[Value], distinct_count: usize, omitted: usize, null_count: usize) -> Value {
+    let mut type_counts: IndexMap<&'static str, usize> = IndexMap::new();
+    let mut number_range: Option<(f64, &Value, f64, &Value)> = None;
+    let mut length_range: Option<(usize, usize)> = None;
+
+    for item in list {
+        let type_name = match item {
+            Value::Null => continue,
+            Value::Bool(_) => "bool",
+            Value::String(s) => {
+                let len = s.chars().count();
+                length_range = Some(match length_range {
+                    Some((min, max)) => (min.min(len), max.max(len)),
+                    None => (len, len),
+                });
+                "str"
+            }
+            Value::Number(n) => {
+                if let Some(x) = n.as_f64() {
+                    number_range = Some(match number_range {
+                        Some((min, min_v, max, max_v)) => {
+                            let (min, min_v) = if x < min { (x, item) } else { (min, min_v) };
+                            let (max, max_v) = if x > max { (x, item) } else { (max, max_v) };
+                            (min, min_v, max, max_v)
+                        }
+                        None => (x, item, x, item),
+                    });
+                }
+                if n.is_f64() { "float" } else { "int" }
+            }
+            Value::Object(_) | Value::Array(_) => continue,
+        };
+        *type_counts.entry(type_name).or_insert(0) += 1;
+    }
+
+    let mut summary = Map::new();
+    summary.insert("item_count".to_string(), json!(list.len()));
+    summary.insert("distinct_count".to_string(), json!(distinct_count));
+    summary.insert("omitted_distinct".to_string(), json!(omitted));
+    summary.insert("null_count".to_string(), json!(null_count));
+    let types: Map<String, Value> = type_counts
+        .into_iter()
+        .map(|(type_name, count)| (type_name.to_string(), json!(count)))
+        .collect();
+    summary.insert("types".to_string(), Value::Object(types));
+    if let Some((_, min, _, max)) = number_range {
+        summary.insert("number_range".to_string(), json!({ "min": min, "max": max }));
+    }
+    if let Some((min, max)) = length_range {
+        summary.insert("string_length_range".to_string(), json!({ "min": min, "max": max }));
+    }
+    Value::Object(summary)
+}
+
 fn distill_recursive(
     original_container: &Value,
-    strict_typing: bool,
-    _repeat_threshold: usize,
+    ctx: &DistillContext,
     memoized_examples: &mut MemoCache,
-    structure_cache: &mut StructureCache,
     depth: usize,
     min_depths: &FxHashMap<String, usize>,
-    position_dependent: bool,
     global_examples_shown: &mut FxHashMap<String, usize>, // Matches Python's global_examples_tracker
+    sensitive: bool, // Under a key matching a redaction pattern: redact every value
 ) -> Result<Value> {
+    if ctx.is_stubbed(depth) && matches!(original_container, Value::Object(_) | Value::Array(_)) {
+        let key = structure_key(original_container, ctx.options, ctx.interner)?;
+        if let Some(stub) = ctx.stub(original_container, &key) {
+            return Ok(stub);
+        }
+    }
     match original_container {
         Value::Object(map) => {
             // Optimization: Pre-allocate with exact capacity
             let mut new_map = Map::with_capacity(map.len());
             for (k, v_original) in map {
+                let sensitive = sensitive
+                    || ctx.options.redaction.as_ref().is_some_and(|r| r.is_sensitive_key(k));
                 new_map.insert(
                     k.clone(),
-                    distill_recursive(v_original, strict_typing, _repeat_threshold, memoized_examples, structure_cache, depth + 1, min_depths, position_dependent, global_examples_shown)?
+                    distill_recursive(v_original, ctx, memoized_examples, depth + 1, min_depths, global_examples_shown, sensitive)?
                 );
             }
             Ok(Value::Object(new_map))
@@ -409,53 +982,22 @@ fn distill_recursive(
             });
 
             if is_list_of_primitives {
-                // Collect unique values
-                let mut unique_values: FxHashSet<Value> = FxHashSet::default();
-                for item in original_list {
-                    unique_values.insert(item.clone());
-                }
-
-                // Sort values (null at end)
-                let mut sorted_values: Vec<Value> = unique_values.into_iter()
-                    .filter(|v| !v.is_null())
-                    .collect();
-
-                // Sort using JSON string representation for consistent ordering
-                sorted_values.sort_by(|a, b| {
-                    match (a, b) {
-                        (Value::Number(n1), Value::Number(n2)) => {
-                            n1.to_string().cmp(&n2.to_string())
-                        }
-                        (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
-                        (Value::Bool(b1), Value::Bool(b2)) => b1.cmp(b2),
-                        _ => serde_json::to_string(a).unwrap_or_default()
-                            .cmp(&serde_json::to_string(b).unwrap_or_default())
-                    }
-                });
-
-                // Add nulls at end
-                let null_count = original_list.iter().filter(|v| v.is_null()).count();
-                for _ in 0..null_count {
-                    sorted_values.push(Value::Null);
-                }
-
-                return Ok(Value::Array(sorted_values));
+                return Ok(ctx.emit_primitive_list(distill_primitive_list(original_list, ctx.options), sensitive));
             }
 
             // Normal distillation for lists of objects/arrays
             // Use IndexMap to preserve insertion order (matches Python dict behavior)
-            let mut hash_sequence: Vec<String> = Vec::with_capacity(original_list.len());
             let mut first_occurrence_indices: IndexMap<String, usize> = IndexMap::with_capacity(original_list.len() / 10);
             // Create LOCAL first_examples for this array (like Python's first_items_to_distill)
             // This ensures each depth level gets its own examples, not global ones
             let mut local_first_examples: IndexMap<String, Value> = IndexMap::new();
 
             // First pass: compute hashes and track first occurrences
-            for (i, item) in original_list.iter().enumerate() {
-                let deep_key = get_deep_structure_key_cached(item, strict_typing, structure_cache)?;
-                let current_hash = generate_hash(&deep_key)?;
-                hash_sequence.push(current_hash.clone());
-
+            let item_keys = hash_list_item_keys(original_list, ctx)?;
+            let hash_sequence: Vec<String> = item_keys.iter().map(|key| key.hash().to_string()).collect();
+            // Tuple rows are shown intact rather than as primitive lists of their own
+            let tuple_signature = ctx.tuple_signature(original_list, &item_keys);
+            for (i, (item, current_hash)) in original_list.iter().zip(&hash_sequence).enumerate() {
                 first_occurrence_indices.entry(current_hash.clone()).or_insert_with(|| {
                     local_first_examples.entry(current_hash.clone()).or_insert_with(|| item.clone());
                     i
@@ -472,7 +1014,10 @@ fn distill_recursive(
                 // example_index is 0 since we only show one example per hash (MAX_EXAMPLES_PER_STRUCTURE=1)
                 let memo_key = (false, hash.clone(), depth, 0);
 
-                if let Some(cached_value) = memoized_examples.get(&memo_key) {
+                // Examples under sensitive keys are redacted differently, so they aren't shared
+                let cached = if sensitive { None } else { memoized_examples.get(&memo_key) };
+                if let Some((cached_value, redactions)) = cached {
+                    ctx.redactions.fetch_add(*redactions, Ordering::Relaxed);
                     distilled_first_examples.insert(hash.clone(), cached_value.clone());
                 } else {
                     // Get the original item from LOCAL cache (matches Python's per-depth behavior)
@@ -480,18 +1025,34 @@ fn distill_recursive(
                         .ok_or_else(|| DistillError::Internal(format!("Original first example missing for hash {}", hash)))?
                         .clone();
 
-                    let distilled_value = distill_recursive(
-                        &original_item,
-                        strict_typing,
-                        _repeat_threshold,
-                        memoized_examples,
-                        structure_cache,
-                        depth + 1,
-                        min_depths,
-                        position_dependent,
-                        global_examples_shown
-                    )?;
-                    memoized_examples.insert(memo_key, distilled_value.clone());
+                    // Stubs of list items carry the hash the list gave them (tuples included)
+                    let stub = ctx
+                        .is_stubbed(depth + 1)
+                        .then(|| ctx.stub(&original_item, &item_keys[first_occurrence_indices[hash]]))
+                        .flatten();
+                    if let Some(stub) = stub {
+                        memoized_examples.insert(memo_key, (stub.clone(), 0));
+                        distilled_first_examples.insert(hash.clone(), stub);
+                        continue;
+                    }
+
+                    let redactions_before = ctx.redactions.load(Ordering::Relaxed);
+                    let distilled_value = match &tuple_signature {
+                        Some(signature) => ctx.emit_tuple(&original_item, signature, sensitive),
+                        None => distill_recursive(
+                            &original_item,
+                            ctx,
+                            memoized_examples,
+                            depth + 1,
+                            min_depths,
+                            global_examples_shown,
+                            sensitive,
+                        )?,
+                    };
+                    if !sensitive {
+                        let redactions = ctx.redactions.load(Ordering::Relaxed) - redactions_before;
+                        memoized_examples.insert(memo_key, (distilled_value.clone(), redactions));
+                    }
                     distilled_first_examples.insert(hash.clone(), distilled_value);
                 }
             }
@@ -540,7 +1101,7 @@ fn distill_recursive(
 
                 // Determine whether to show example based on position_dependent mode
                 // Matches Python's logic exactly
-                let should_show_example = if position_dependent {
+                let should_show_example = if ctx.options.position_dependent {
                     // Position-dependent: show examples independently at each depth level
                     is_first
                 } else {
@@ -573,18 +1134,16 @@ fn distill_recursive(
             // Label first examples that appear in summaries
             for (hash_str, index_in_new_list) in &first_item_positions {
                 if hashes_referenced_in_summaries.contains(hash_str) {
-                    if let Some(item_to_label) = new_list.get_mut(*index_in_new_list) {
-                        if let Value::Object(obj_map) = item_to_label {
-                            obj_map.entry("_structure_hash".to_string())
-                                .or_insert_with(|| Value::String(hash_str.clone()));
-                        }
+                    if let Some(Value::Object(obj_map)) = new_list.get_mut(*index_in_new_list) {
+                        obj_map.entry("_structure_hash".to_string())
+                            .or_insert_with(|| Value::String(hash_str.clone()));
                     }
                 }
             }
 
             Ok(Value::Array(new_list))
         }
-        primitive => Ok(primitive.clone()),
+        primitive => Ok(ctx.emit(primitive, sensitive)),
     }
 }
 
@@ -594,31 +1153,77 @@ pub fn distill_json(
     repeat_threshold: usize,
     position_dependent: bool,
 ) -> Result<Value> {
+    let options = DistillOptions {
+        strict_typing,
+        repeat_threshold,
+        position_dependent,
+        ..DistillOptions::default()
+    };
+    distill_json_with_options(json_data, &options, &DistillControl::default())
+}
+
+/// Same as `distill_json`, taking all options at once and reporting progress /
+/// honoring cancellation through `control`
+pub fn distill_json_with_options(
+    json_data: Value,
+    options: &DistillOptions,
+    control: &DistillControl,
+) -> Result<Value> {
+    options.hash_scheme.validate()?;
+    distill_json_with_cache(json_data, options, control, &StructureCache::new(options.hash_scheme))
+}
+
+/// Same as `distill_json_with_options`, reusing (and filling) a structure cache
+pub fn distill_json_with_cache(
+    json_data: Value,
+    options: &DistillOptions,
+    control: &DistillControl,
+    cache: &StructureCache,
+) -> Result<Value> {
+    let strict_typing = options.strict_typing;
+    let position_dependent = options.position_dependent;
+    let interner = cache.interner_for(options)?;
+    // Ignored members don't change structure keys, so dropping them up front only
+    // changes what the examples show
+    let json_data = match &options.ignore {
+        Some(ignore) if ignore.from_examples => {
+            let mut json_data = json_data;
+            ignore.prune(&mut json_data);
+            json_data
+        }
+        _ => json_data,
+    };
+
     // Use IndexMap for insertion-order preservation (matches Python behavior)
     let mut memoized_examples: MemoCache = IndexMap::new();
-    let mut structure_cache: StructureCache = FxHashMap::default();
     // Global counter for examples shown (matches Python's global_examples_tracker)
     let mut global_examples_shown: FxHashMap<String, usize> = FxHashMap::default();
 
+    let ctx = DistillContext {
+        options,
+        control,
+        interner,
+        redactions: AtomicUsize::new(0),
+        tuples: Mutex::new(IndexSet::new()),
+    };
+
     // Pass 1: Collect minimum depths for each hash (when position_dependent=false)
     let mut min_depths: FxHashMap<String, usize> = FxHashMap::default();
     if !position_dependent && matches!(json_data, Value::Object(_) | Value::Array(_)) {
-        collect_structure_depths(&json_data, 0, strict_typing, &mut structure_cache, &mut min_depths)?;
+        collect_structure_depths(&json_data, 0, &ctx, &mut min_depths)?;
     }
 
     let distilled_data = distill_recursive(
         &json_data,
-        strict_typing,
-        repeat_threshold,
+        &ctx,
         &mut memoized_examples,
-        &mut structure_cache,
         0,
         &min_depths,
-        position_dependent,
         &mut global_examples_shown,
+        false,
     )?;
 
-    let description = format!(
+    let mut description = format!(
         "Distilled JSON structure. Shows the first encountered example for each unique deep structure within lists.
 POSITION_DEPENDENT mode: {}
   - true: Examples shown independently at each nesting level (predictable, depth-aware).
@@ -631,9 +1236,604 @@ Strict primitive typing for structure detection: {}. Repeat threshold for patter
         if strict_typing { "true" } else { "false" }
     );
 
+    if options.primitive_order == PrimitiveOrder::Preserve {
+        description.push_str(
+            "\nLists of primitives keep their original order. Arrays of one list that share a length and \
+per-position types are positional tuples, kept intact.",
+        );
+    }
+    let tuples: Vec<String> = ctx.tuples.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect();
+    if !tuples.is_empty() {
+        description.push_str(&format!(
+            "\nPositional tuples, shown whole and in their original order: {}.",
+            tuples.join(", ")
+        ));
+    }
+    let max_samples = match options.primitive_order {
+        PrimitiveOrder::Sorted => options.max_primitive_samples,
+        PrimitiveOrder::Preserve => Some(options.max_primitive_samples.unwrap_or(PRESERVED_PRIMITIVES)),
+    };
+    if let Some(max_samples) = max_samples {
+        let kept = match options.primitive_order {
+            PrimitiveOrder::Sorted => "unique values",
+            PrimitiveOrder::Preserve => "elements",
+        };
+        description.push_str(&format!(
+            "\nLists of primitives show at most {} {}; longer lists end with a summary object \
+(item_count, distinct_count, omitted_distinct, null_count, types, number_range, string_length_range).",
+            max_samples, kept
+        ));
+    }
+    if options.typed_primitives {
+        description.push_str(
+            "\nSingle-key objects such as {\"$oid\": ...}, {\"$date\": ...} or {\"$int32\": ...} are typed primitives \
+(ObjectId, datetime, int32, ...) preserved from the source format, not dicts.",
+        );
+    }
+
+    if let Some(max_depth) = options.max_depth {
+        description.push_str(&format!(
+            "\nObjects and arrays more than {} levels deep are replaced by stubs \
+{{\"_stub\": \"object\"|\"array\", \"_structure_hash\", \"keys\"|\"items\" (count), \"signature\" (top level only)}}.",
+            max_depth
+        ));
+    }
+    if let Some(max_len) = options.max_string_len {
+        description.push_str(&format!(
+            "\nExample strings longer than {} characters are cut, ending with '…(+N chars)' (N = characters removed).",
+            max_len
+        ));
+        if options.string_length_stats {
+            description.push_str(
+                " \"string_lengths\" gives the length distribution of every field (path with [] for list items) that had strings cut.",
+            );
+        }
+    }
+    if let Some(ignore) = &options.ignore {
+        description.push_str(&format!(
+            "\nIgnored for structure detection ({}): {}. Paths start at any object and don't cross lists.",
+            if ignore.from_examples { "and removed from examples" } else { "still shown in examples" },
+            ignore.describe()
+        ));
+    }
+    if let Some(redaction) = &options.redaction {
+        let detectors: Vec<String> = redaction.detectors.iter().map(|d| d.to_string()).collect();
+        description.push_str(&format!(
+            "\nSensitive example values are redacted ({} strategy): everything under keys containing {}, \
+and values detected as {}. redacted_values counts the replacements.",
+            redaction.strategy,
+            if redaction.key_patterns.is_empty() { "(none)".to_string() } else { redaction.key_patterns.join("/") },
+            if detectors.is_empty() { "(none)".to_string() } else { detectors.join("/") },
+        ));
+    }
+
     let mut final_output_map = Map::new();
     final_output_map.insert("description".to_string(), Value::String(description));
+    final_output_map.insert("hash_scheme".to_string(), Value::String(options.hash_scheme.to_string()));
+    if options.redaction.is_some() {
+        final_output_map.insert("redacted_values".to_string(), json!(ctx.redactions.load(Ordering::Relaxed)));
+    }
+    if let Some(max_len) = options.max_string_len.filter(|_| options.string_length_stats) {
+        final_output_map.insert("string_lengths".to_string(), string_length_report(&json_data, max_len));
+    }
+    let collisions = interner.collision_report();
+    if !collisions.is_empty() {
+        final_output_map.insert("hash_collisions".to_string(), Value::Array(collisions));
+    }
     final_output_map.insert("distilled_data".to_string(), distilled_data);
 
     Ok(Value::Object(final_output_map))
 }
+
+/// The option-dependent sentences of a distilled output's description (depth limit,
+/// ignored keys, redaction, ...): its lines after the fixed introduction
+pub(crate) fn description_notes(description: &str) -> impl Iterator<Item = &str> {
+    description.lines().skip_while(|line| !line.starts_with("Strict primitive typing")).skip(1)
+}
+
+/// Raw items matching a structure hash, as returned by `find_structure_items`
+pub struct StructureMatches {
+    /// Total number of items with the requested hash anywhere in the document
+    pub total: usize,
+    /// (JSON Pointer, raw item) pairs in document order
+    pub items: Vec<(String, Value)>,
+}
+
+/// Escape a key for use as a JSON Pointer reference token (RFC 6901)
+pub(crate) fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Find list items whose deep structure hash equals `target_hash`.
+/// Only lists of objects/arrays are considered (the same lists distill_recursive summarizes),
+/// so every hash shown in a distilled output can be expanded back to its raw items.
+/// If no list item matches, objects and arrays anywhere else (object members, the root)
+/// are searched, which is where `max_depth` stubs of non-list values come from.
+pub fn find_structure_items(
+    json_data: &Value,
+    target_hash: &str,
+    options: &DistillOptions,
+    offset: usize,
+    limit: usize,
+    control: &DistillControl,
+) -> Result<StructureMatches> {
+    options.hash_scheme.validate()?;
+    let interner = StructureInterner::new(options.hash_scheme);
+    let mut matches = StructureMatches { total: 0, items: Vec::with_capacity(limit.min(64)) };
+    let mut pointer = String::new();
+    collect_structure_items(
+        json_data,
+        target_hash,
+        KeyOptions::from(options),
+        offset,
+        limit,
+        &mut pointer,
+        &interner,
+        &mut matches,
+        control,
+    )?;
+    if matches.total == 0 {
+        collect_container_matches(
+            json_data,
+            target_hash,
+            options,
+            offset,
+            limit,
+            &mut pointer,
+            &interner,
+            &mut matches,
+            control,
+        )?;
+    }
+    Ok(matches)
+}
+
+/// Like `collect_structure_items`, matching every object and array by its own structure
+/// key (list context such as tuple detection doesn't apply outside lists)
+#[allow(clippy::too_many_arguments)]
+fn collect_container_matches(
+    value: &Value,
+    target_hash: &str,
+    options: &DistillOptions,
+    offset: usize,
+    limit: usize,
+    pointer: &mut String,
+    interner: &StructureInterner,
+    matches: &mut StructureMatches,
+    control: &DistillControl,
+) -> Result<()> {
+    if !matches!(value, Value::Object(_) | Value::Array(_)) {
+        return Ok(());
+    }
+    control.tick()?;
+    if structure_key(value, options, interner)?.hash() == target_hash {
+        if matches.total >= offset && matches.items.len() < limit {
+            matches.items.push((pointer.clone(), value.clone()));
+        }
+        matches.total += 1;
+    }
+    let prefix_len = pointer.len();
+    let children: Box<dyn Iterator<Item = (String, &Value)>> = match value {
+        Value::Object(map) => Box::new(map.iter().map(|(k, v)| (escape_pointer_token(k), v))),
+        Value::Array(list) => Box::new(list.iter().enumerate().map(|(i, v)| (i.to_string(), v))),
+        _ => Box::new(std::iter::empty()),
+    };
+    for (token, child) in children {
+        pointer.push('/');
+        pointer.push_str(&token);
+        collect_container_matches(child, target_hash, options, offset, limit, pointer, interner, matches, control)?;
+        pointer.truncate(prefix_len);
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collect_structure_items(
+    container: &Value,
+    target_hash: &str,
+    keys: KeyOptions,
+    offset: usize,
+    limit: usize,
+    pointer: &mut String,
+    interner: &StructureInterner,
+    matches: &mut StructureMatches,
+    control: &DistillControl,
+) -> Result<()> {
+    let prefix_len = pointer.len();
+    match container {
+        Value::Object(map) => {
+            for (k, v) in map {
+                pointer.push('/');
+                pointer.push_str(&escape_pointer_token(k));
+                collect_structure_items(v, target_hash, keys, offset, limit, pointer, interner, matches, control)?;
+                pointer.truncate(prefix_len);
+            }
+        }
+        Value::Array(list) => {
+            let is_list_of_primitives = list.iter().all(|item| {
+                !matches!(item, Value::Object(_) | Value::Array(_))
+            });
+            if is_list_of_primitives {
+                return Ok(());
+            }
+
+            let item_keys = list_item_keys(list, keys, interner)?;
+            for (i, (item, deep_key)) in list.iter().zip(item_keys).enumerate() {
+                control.tick()?;
+                pointer.push('/');
+                pointer.push_str(&i.to_string());
+
+                if deep_key.hash() == target_hash {
+                    if matches.total >= offset && matches.items.len() < limit {
+                        matches.items.push((pointer.clone(), item.clone()));
+                    }
+                    matches.total += 1;
+                }
+
+                collect_structure_items(item, target_hash, keys, offset, limit, pointer, interner, matches, control)?;
+                pointer.truncate(prefix_len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// One entry of the structure catalog built by `structure_catalog`
+struct CatalogEntry {
+    signature: String,
+    count: usize,
+    min_depth: usize,
+    first_path: String,
+}
+
+/// Build a catalog of every structure hash that appears in lists of objects/arrays.
+/// Entries are returned in first-seen order with occurrence counts, the shallowest depth
+/// and the JSON Pointer of the first occurrence, so hashes in a distilled output can be
+/// looked up without reading the raw document.
+pub fn structure_catalog(json_data: &Value, options: &DistillOptions, control: &DistillControl) -> Result<Value> {
+    options.hash_scheme.validate()?;
+    structure_catalog_with_cache(json_data, options, control, &StructureCache::new(options.hash_scheme))
+}
+
+/// Same as `structure_catalog`, reusing (and filling) a structure cache
+pub fn structure_catalog_with_cache(
+    json_data: &Value,
+    options: &DistillOptions,
+    control: &DistillControl,
+    cache: &StructureCache,
+) -> Result<Value> {
+    let interner = cache.interner_for(options)?;
+    let mut entries: IndexMap<String, CatalogEntry> = IndexMap::new();
+    let mut pointer = String::new();
+    collect_catalog_entries(json_data, 0, KeyOptions::from(options), &mut pointer, interner, &mut entries, control)?;
+
+    let catalog: Vec<Value> = entries
+        .into_iter()
+        .map(|(hash, entry)| json!({
+            "hash": hash,
+            "signature": entry.signature,
+            "count": entry.count,
+            "min_depth": entry.min_depth,
+            "first_path": entry.first_path,
+        }))
+        .collect();
+    Ok(Value::Array(catalog))
+}
+
+/// Per-structure totals across the files of a collection, built by `distill_collection`
+struct CollectionEntry {
+    signature: String,
+    count: usize,
+    files: usize,
+    example_files: Vec<String>,
+}
+
+/// Number of file names listed per structure in a collection report
+const COLLECTION_EXAMPLE_FILES: usize = 3;
+
+/// Distill many documents (one per input file) as one virtual list, so structures are
+/// hashed across files. The output gains a "collection" section listing, for every
+/// structure hash, how many files it appears in (most widespread first), its total
+/// occurrence count and a few of the files that contain it.
+pub fn distill_collection(
+    documents: Vec<(String, Value)>,
+    options: &DistillOptions,
+    control: &DistillControl,
+) -> Result<Value> {
+    options.hash_scheme.validate()?;
+    distill_collection_with_cache(documents, options, control, &StructureCache::new(options.hash_scheme))
+}
+
+/// Same as `distill_collection`, reusing (and filling) a structure cache
+pub fn distill_collection_with_cache(
+    documents: Vec<(String, Value)>,
+    options: &DistillOptions,
+    control: &DistillControl,
+    cache: &StructureCache,
+) -> Result<Value> {
+    let interner = cache.interner_for(options)?;
+    let (names, values): (Vec<String>, Vec<Value>) = documents.into_iter().unzip();
+    let keys = KeyOptions::from(options);
+
+    let mut report: IndexMap<String, CollectionEntry> = IndexMap::new();
+    let document_keys = list_item_keys(&values, keys, interner)?;
+    for ((name, document), document_key) in names.iter().zip(&values).zip(document_keys) {
+        control.tick()?;
+        // Each document is an item of the virtual list, then everything nested in it
+        let mut entries: IndexMap<String, CatalogEntry> = IndexMap::new();
+        if matches!(document, Value::Object(_) | Value::Array(_)) {
+            entries.insert(document_key.hash().to_string(), CatalogEntry {
+                signature: document_key.signature(),
+                count: 1,
+                min_depth: 0,
+                first_path: String::new(),
+            });
+        }
+        let mut pointer = String::new();
+        collect_catalog_entries(document, 1, keys, &mut pointer, interner, &mut entries, control)?;
+
+        for (hash, entry) in entries {
+            let totals = report.entry(hash).or_insert_with(|| CollectionEntry {
+                signature: entry.signature,
+                count: 0,
+                files: 0,
+                example_files: Vec::new(),
+            });
+            totals.count += entry.count;
+            totals.files += 1;
+            if totals.example_files.len() < COLLECTION_EXAMPLE_FILES {
+                totals.example_files.push(name.clone());
+            }
+        }
+    }
+    // Stable sort: ties keep first-seen order
+    report.sort_by(|_, a, _, b| b.files.cmp(&a.files));
+
+    let structures: Vec<Value> = report
+        .into_iter()
+        .map(|(hash, entry)| json!({
+            "hash": hash,
+            "signature": entry.signature,
+            "files": entry.files,
+            "count": entry.count,
+            "example_files": entry.example_files,
+        }))
+        .collect();
+    let collection = json!({
+        "files": names.len(),
+        "structures": structures,
+    });
+
+    let mut distilled = distill_json_with_cache(Value::Array(values), options, control, cache)?;
+    if let Value::Object(map) = &mut distilled {
+        if let Some(Value::String(description)) = map.get_mut("description") {
+            description.push_str(&format!(
+                "\ndistilled_data is a list with one item per input file ({} files). \
+\"collection\" lists every structure hash with the number of files it appears in.",
+                names.len()
+            ));
+        }
+        let index = map.keys().position(|k| k == "distilled_data").unwrap_or(map.len());
+        map.shift_insert(index, "collection".to_string(), collection);
+    }
+    Ok(distilled)
+}
+
+fn collect_catalog_entries(
+    container: &Value,
+    depth: usize,
+    keys: KeyOptions,
+    pointer: &mut String,
+    interner: &StructureInterner,
+    entries: &mut IndexMap<String, CatalogEntry>,
+    control: &DistillControl,
+) -> Result<()> {
+    let prefix_len = pointer.len();
+    match container {
+        Value::Object(map) => {
+            for (k, v) in map {
+                pointer.push('/');
+                pointer.push_str(&escape_pointer_token(k));
+                collect_catalog_entries(v, depth + 1, keys, pointer, interner, entries, control)?;
+                pointer.truncate(prefix_len);
+            }
+        }
+        Value::Array(list) => {
+            let is_list_of_primitives = list.iter().all(|item| {
+                !matches!(item, Value::Object(_) | Value::Array(_))
+            });
+            if is_list_of_primitives {
+                return Ok(());
+            }
+
+            let item_keys = list_item_keys(list, keys, interner)?;
+            for (i, (item, deep_key)) in list.iter().zip(item_keys).enumerate() {
+                control.tick()?;
+                pointer.push('/');
+                pointer.push_str(&i.to_string());
+
+                let current_hash = deep_key.hash().to_string();
+                entries
+                    .entry(current_hash)
+                    .and_modify(|entry| {
+                        entry.count += 1;
+                        entry.min_depth = entry.min_depth.min(depth);
+                    })
+                    .or_insert_with(|| CatalogEntry {
+                        signature: deep_key.signature(),
+                        count: 1,
+                        min_depth: depth,
+                        first_path: pointer.clone(),
+                    });
+
+                collect_catalog_entries(item, depth + 1, keys, pointer, interner, entries, control)?;
+                pointer.truncate(prefix_len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(n: usize) -> Value {
+        Value::Array((0..n).map(|i| json!({ "id": i, "tags": ["a", "b"] })).collect())
+    }
+
+    /// A list long enough to be hashed in parallel, mixing shapes, key orders and nesting
+    fn mixed_list() -> Value {
+        Value::Array(
+            (0..PARALLEL_MIN_ITEMS * 4)
+                .map(|i| match i % 5 {
+                    0 => json!({ "id": i, "name": "a" }),
+                    1 => json!({ "name": "b", "id": i }),
+                    2 => json!({ "id": i, "children": items(i % 3) }),
+                    3 => json!([i, "x", { "deep": [1.5, null] }]),
+                    _ => json!({ "id": i as f64 + 0.5, "name": null }),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parallel_and_sequential_output_are_identical() {
+        for position_dependent in [false, true] {
+            let run = |parallel| {
+                let options = DistillOptions { parallel, position_dependent, ..DistillOptions::default() };
+                distill_json_with_options(json!({ "records": mixed_list() }), &options, &DistillControl::default())
+                    .unwrap()
+            };
+            assert_eq!(run(true), run(false));
+        }
+    }
+
+    #[test]
+    fn key_order_is_part_of_the_structure_whichever_order_comes_first() {
+        let options = DistillOptions::default();
+        let signatures = |list: Value| {
+            let catalog = structure_catalog(&list, &options, &DistillControl::default()).unwrap();
+            catalog.as_array().unwrap().iter().map(|e| e["signature"].as_str().unwrap().to_string()).collect::<Vec<_>>()
+        };
+        let ab = json!({ "a": 1, "b": 2 });
+        let ba = json!({ "b": 2, "a": 1 });
+        assert_eq!(signatures(json!([ab, ba])), ["{a: int, b: int}", "{b: int, a: int}"]);
+        assert_eq!(signatures(json!([ba, ab])), ["{b: int, a: int}", "{a: int, b: int}"]);
+    }
+
+    #[test]
+    fn cancelled_control_stops_drill_down_passes() {
+        let control = DistillControl::default();
+        control.cancel();
+        let options = DistillOptions::default();
+        let data = json!({ "items": items(3) });
+        assert!(matches!(structure_catalog(&data, &options, &control), Err(DistillError::Cancelled)));
+        assert!(matches!(
+            find_structure_items(&data, "00000000", &options, 0, 5, &control),
+            Err(DistillError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn drill_down_passes_report_progress() {
+        let control = DistillControl::default();
+        let options = DistillOptions::default();
+        let data = json!({ "items": items(3) });
+        let catalog = structure_catalog(&data, &options, &control).unwrap();
+        let hash = catalog[0]["hash"].as_str().unwrap().to_string();
+        let matches = find_structure_items(&data, &hash, &options, 1, 5, &control).unwrap();
+        assert_eq!(matches.total, 3);
+        assert_eq!(matches.items.iter().map(|(path, _)| path.as_str()).collect::<Vec<_>>(), ["/items/1", "/items/2"]);
+        assert!(control.items_hashed() >= 6);
+    }
+
+    fn distilled(data: Value, options: &DistillOptions) -> Value {
+        distill_json_with_options(data, options, &DistillControl::default()).unwrap()
+    }
+
+    #[test]
+    fn preserve_mode_keeps_tuple_rows_and_caps_other_lists() {
+        let options = DistillOptions { primitive_order: PrimitiveOrder::Preserve, ..DistillOptions::default() };
+        let rows: Vec<Value> = (0..3).map(|i| json!([i, "a", 2.5, true, null, 1, 2, 3, 4, 5, 6, 7])).collect();
+        let output = distilled(
+            json!({ "rows": rows, "series": (0..15).rev().collect::<Vec<i32>>(), "pair": [3, 1] }),
+            &options,
+        );
+        let data = &output["distilled_data"];
+
+        // Twelve positions, longer than the first-N cap, yet shown whole and in order
+        assert_eq!(data["rows"][0], rows[0]);
+        let description = output["description"].as_str().unwrap();
+        assert!(description.contains("[int, str, float, bool, NoneType, int, int, int, int, int, int, int]"));
+
+        let series = data["series"].as_array().unwrap();
+        assert_eq!(series.len(), PRESERVED_PRIMITIVES + 1);
+        assert_eq!(series[..3], [json!(14), json!(13), json!(12)]);
+        assert_eq!(series[PRESERVED_PRIMITIVES]["item_count"], 15);
+        assert_eq!(data["pair"], json!([3, 1]));
+    }
+
+    #[test]
+    fn preserve_mode_needs_a_shared_length_and_position_types_for_tuples() {
+        let options = DistillOptions {
+            primitive_order: PrimitiveOrder::Preserve,
+            max_primitive_samples: Some(2),
+            ..DistillOptions::default()
+        };
+        let lengths = distilled(json!({ "rows": [[3, 2, 1], [1, 2]] }), &options);
+        assert_eq!(lengths["distilled_data"]["rows"][0].as_array().unwrap()[..2], [json!(3), json!(2)]);
+        assert_eq!(lengths["distilled_data"]["rows"][0][2]["item_count"], 3);
+
+        let types = distilled(json!({ "rows": [[3, 2, 1], [1, "b", 2]] }), &options);
+        assert_eq!(types["distilled_data"]["rows"][0][2]["item_count"], 3);
+        assert!(!types["description"].as_str().unwrap().contains("Positional tuples"));
+
+        let tuples = distilled(json!({ "rows": [[3, 2, 1], [1, 2, 3]] }), &options);
+        assert_eq!(tuples["distilled_data"]["rows"][0], json!([3, 2, 1]));
+        assert!(tuples["description"].as_str().unwrap().contains("original order: [int, int, int]."));
+    }
+
+    #[test]
+    fn detected_tuples_keep_their_order_whatever_their_length() {
+        let options = DistillOptions { detect_tuples: true, ..DistillOptions::default() };
+        let row = json!([1, "a", 2.5, true, null]);
+        let output = distilled(json!({ "rows": [row, [2, "b", 3.5, false, null]] }), &options);
+        assert_eq!(output["distilled_data"]["rows"][0], row);
+        assert!(output["description"].as_str().unwrap().contains("order: [int, str, float, bool, NoneType]."));
+    }
+
+    #[test]
+    fn detected_tuples_leave_other_short_lists_sorted() {
+        let options = DistillOptions { detect_tuples: true, ..DistillOptions::default() };
+        let output = distilled(json!({ "tags": ["c", "a", "b"], "rows": [[3, 2, 1], [1, 2]] }), &options);
+        assert_eq!(output["distilled_data"]["tags"], json!(["a", "b", "c"]));
+        // Different lengths: not tuples, so each row is an ordinary sorted list
+        assert_eq!(output["distilled_data"]["rows"][0], json!([1, 2, 3]));
+        assert!(!output["description"].as_str().unwrap().contains("Positional tuples"));
+    }
+
+    #[test]
+    fn max_depth_keeps_that_many_levels_below_the_root() {
+        let data = json!({ "a": { "b": { "c": 1 } }, "list": [{ "x": 1 }], "n": 1 });
+        let at = |max_depth| {
+            distilled(data.clone(), &DistillOptions { max_depth: Some(max_depth), ..DistillOptions::default() })
+        };
+
+        let root_only = at(0);
+        assert_eq!(root_only["distilled_data"]["n"], 1);
+        assert_eq!(root_only["distilled_data"]["a"]["_stub"], "object");
+        assert_eq!(root_only["distilled_data"]["list"]["_stub"], "array");
+
+        let one = at(1)["distilled_data"].clone();
+        assert_eq!(one["a"]["b"]["_stub"], "object");
+        assert_eq!(one["list"][0]["_stub"], "object");
+        assert!(at(1)["description"].as_str().unwrap().contains("more than 1 levels deep"));
+
+        let two = at(2)["distilled_data"].clone();
+        assert_eq!(two["a"], json!({ "b": { "c": 1 } }));
+        assert_eq!(two["list"][0], json!({ "x": 1 }));
+    }
+}
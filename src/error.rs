@@ -10,6 +10,18 @@ pub enum DistillError {
     #[error("JSON Parsing Error: {0}")]
     JsonParse(#[from] serde_json::Error),
 
+    #[error("YAML Parsing Error: {0}")]
+    YamlParse(#[from] serde_yaml::Error),
+
+    #[error("TOML Parsing Error: {0}")]
+    TomlParse(#[from] toml::de::Error),
+
+    #[error("JSON5 Parsing Error: {0}")]
+    Json5Parse(#[from] json5::Error),
+
+    #[error("Binary Decoding Error: {0}")]
+    BinaryDecode(String),
+
     #[error("Invalid Input: {0}")]
     InvalidInput(String),
 
@@ -17,6 +29,9 @@ pub enum DistillError {
     #[error("Hashing Error: {0}")]
     HashingError(String),
 
+    #[error("Distillation cancelled")]
+    Cancelled,
+
     #[error("Internal Error: {0}")]
     Internal(String),
 }
@@ -0,0 +1,373 @@
+// src/har.rs
+
+use crate::core::{self, DistillControl, DistillOptions};
+use crate::error::{DistillError, Result};
+use crate::redact;
+use base64::Engine;
+use indexmap::{IndexMap, IndexSet};
+use serde_json::{json, Map, Value};
+
+/// Request/response pairs of one endpoint (method + URL template) collected from HAR files
+#[derive(Debug, Default)]
+pub struct HarEndpoint {
+    pub method: String,
+    /// Scheme and host, e.g. `https://api.example.com`
+    pub origin: String,
+    /// Path with identifiers replaced by placeholders, e.g. `/users/{id}`
+    pub path_template: String,
+    /// Number of requests seen
+    pub requests: usize,
+    /// Requests per response status
+    pub statuses: IndexMap<u16, usize>,
+    /// JSON request bodies, in HAR order
+    pub request_bodies: Vec<Value>,
+    /// JSON response bodies with the status they were returned with, in HAR order
+    pub response_bodies: Vec<(Option<u16>, Value)>,
+    /// Example concrete URL (first seen)
+    pub example_url: String,
+}
+
+impl HarEndpoint {
+    /// `GET https://api.example.com/users/{id}`
+    pub fn name(&self) -> String {
+        format!("{} {}{}", self.method, self.origin, self.path_template)
+    }
+}
+
+/// Group the entries of one or more HAR documents by method, origin and URL template.
+/// Entries without a JSON request or response body are still counted; bodies that
+/// aren't JSON are skipped. Endpoints are returned in first-seen order.
+pub fn extract_endpoints<'a>(hars: impl IntoIterator<Item = &'a Value>) -> Result<Vec<HarEndpoint>> {
+    let mut endpoints: IndexMap<(String, String, String), HarEndpoint> = IndexMap::new();
+
+    for har in hars {
+        let entries = har
+            .pointer("/log/entries")
+            .and_then(|e| e.as_array())
+            .ok_or_else(|| DistillError::InvalidInput("Not a HAR document: missing log.entries".to_string()))?;
+
+        for entry in entries {
+            let method = entry
+                .pointer("/request/method")
+                .and_then(|m| m.as_str())
+                .unwrap_or("GET")
+                .to_ascii_uppercase();
+            let url = entry.pointer("/request/url").and_then(|u| u.as_str()).unwrap_or_default();
+            let (origin, path) = split_url(url);
+            let path_template = url_template(path);
+
+            let endpoint = endpoints
+                .entry((method.clone(), origin.to_string(), path_template.clone()))
+                .or_insert_with(|| HarEndpoint {
+                    method,
+                    origin: origin.to_string(),
+                    path_template,
+                    example_url: url.to_string(),
+                    ..HarEndpoint::default()
+                });
+            endpoint.requests += 1;
+            // HAR uses 0 (or -1) for requests that never got a response
+            let status = entry
+                .pointer("/response/status")
+                .and_then(|s| s.as_u64())
+                .filter(|s| *s > 0)
+                .map(|s| s as u16);
+            if let Some(status) = status {
+                *endpoint.statuses.entry(status).or_insert(0) += 1;
+            }
+            if let Some(body) = entry.pointer("/request/postData").and_then(json_body) {
+                endpoint.request_bodies.push(body);
+            }
+            if let Some(body) = entry.pointer("/response/content").and_then(json_body) {
+                endpoint.response_bodies.push((status, body));
+            }
+        }
+    }
+
+    Ok(endpoints.into_values().collect())
+}
+
+/// Parse a HAR `postData` or `content` object as JSON, if it is JSON.
+/// Bodies are accepted when the MIME type mentions json, or when untyped text parses
+/// as a JSON object or array.
+fn json_body(content: &Value) -> Option<Value> {
+    let text = content.get("text")?.as_str()?;
+    let decoded;
+    let text = if content.get("encoding").and_then(|e| e.as_str()) == Some("base64") {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(text.trim()).ok()?;
+        decoded = String::from_utf8(bytes).ok()?;
+        decoded.as_str()
+    } else {
+        text
+    };
+    let mime_type = content.get("mimeType").and_then(|m| m.as_str()).unwrap_or_default();
+    let trimmed = text.trim_start();
+    if !(mime_type.contains("json") || trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return None;
+    }
+    serde_json::from_str(text).ok()
+}
+
+/// Split a URL into origin (`https://host:port`) and path, dropping query and fragment
+fn split_url(url: &str) -> (&str, &str) {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let after_scheme = url.find("://").map(|i| i + 3).unwrap_or(0);
+    match url[after_scheme..].find('/') {
+        Some(i) => url.split_at(after_scheme + i),
+        None => (url, "/"),
+    }
+}
+
+/// Replace identifier-like path segments with placeholders:
+/// numbers become `{id}`, UUIDs `{uuid}`, long hex strings `{hash}`, ISO dates `{date}`
+/// and long tokens mixing letters and digits `{token}`. Repeated placeholders get a
+/// numeric suffix (`/users/{id}/posts/{id2}`) so every path parameter has its own name.
+pub fn url_template(path: &str) -> String {
+    let mut counts: IndexMap<&'static str, usize> = IndexMap::new();
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|segment| match segment_placeholder(segment) {
+            Some(name) => {
+                let count = counts.entry(name).or_insert(0);
+                *count += 1;
+                if *count == 1 {
+                    format!("{{{}}}", name)
+                } else {
+                    format!("{{{}{}}}", name, count)
+                }
+            }
+            None => segment.to_string(),
+        })
+        .collect();
+    segments.join("/")
+}
+
+fn segment_placeholder(segment: &str) -> Option<&'static str> {
+    let is_hex = |s: &str| s.bytes().all(|b| b.is_ascii_hexdigit());
+    let has_digit = segment.bytes().any(|b| b.is_ascii_digit());
+
+    if segment.is_empty() {
+        None
+    } else if segment.bytes().all(|b| b.is_ascii_digit()) {
+        Some("id")
+    } else if segment.len() == 36
+        && segment.split('-').map(str::len).eq([8, 4, 4, 4, 12])
+        && is_hex(&segment.replace('-', ""))
+    {
+        Some("uuid")
+    } else if segment.len() >= 16 && has_digit && is_hex(segment) {
+        Some("hash")
+    } else if segment.len() == 10
+        && segment.split('-').map(str::len).eq([4, 2, 2])
+        && segment.replace('-', "").bytes().all(|b| b.is_ascii_digit())
+    {
+        Some("date")
+    } else if segment.len() >= 20
+        && has_digit
+        && segment.bytes().any(|b| b.is_ascii_alphabetic())
+        && segment.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    {
+        Some("token")
+    } else {
+        None
+    }
+}
+
+/// Distill the JSON bodies of every endpoint found in the HAR documents.
+/// Each endpoint's response bodies (and request bodies, if any) are distilled together
+/// as one list, so the output is a per-endpoint structure catalog.
+pub fn distill_har<'a>(
+    hars: impl IntoIterator<Item = &'a Value>,
+    options: &DistillOptions,
+    control: &DistillControl,
+) -> Result<Value> {
+    options.hash_scheme.validate()?;
+    let endpoints = extract_endpoints(hars)?;
+
+    let mut distilled_endpoints = Vec::with_capacity(endpoints.len());
+    let mut collisions = Vec::new();
+    let mut redacted_values = 0;
+    // Notes on stubs, ignored keys, cut strings, redaction... from every endpoint's description
+    let mut notes: IndexSet<String> = IndexSet::new();
+    for endpoint in endpoints {
+        let name = endpoint.name();
+        let mut entry = Map::new();
+        entry.insert("endpoint".to_string(), Value::String(name.clone()));
+        entry.insert("method".to_string(), Value::String(endpoint.method));
+        entry.insert("origin".to_string(), Value::String(endpoint.origin));
+        entry.insert("path_template".to_string(), Value::String(endpoint.path_template));
+        let example_url = match &options.redaction {
+            Some(redaction) => {
+                let (url, count) = redact::redact_url(&endpoint.example_url, redaction);
+                redacted_values += count as u64;
+                url
+            }
+            None => endpoint.example_url,
+        };
+        entry.insert("example_url".to_string(), Value::String(example_url));
+        entry.insert("requests".to_string(), Value::from(endpoint.requests));
+        let statuses: Map<String, Value> = endpoint
+            .statuses
+            .iter()
+            .map(|(status, count)| (status.to_string(), Value::from(*count)))
+            .collect();
+        entry.insert("statuses".to_string(), Value::Object(statuses));
+
+        let response_bodies = endpoint.response_bodies.into_iter().map(|(_, body)| body).collect();
+        for (label, bodies) in [("request", endpoint.request_bodies), ("response", response_bodies)] {
+            if bodies.is_empty() {
+                continue;
+            }
+            let bodies = Value::Array(bodies);
+            let catalog = core::structure_catalog(&bodies, options, control)?;
+            let count = bodies.as_array().map_or(0, Vec::len);
+            let distilled = core::distill_json_with_options(bodies, options, control)?;
+            if let Some(Value::Array(found)) = distilled.get("hash_collisions") {
+                collisions.extend(found.iter().map(|c| json!({ "endpoint": name, "collision": c })));
+            }
+            redacted_values += distilled.get("redacted_values").and_then(Value::as_u64).unwrap_or(0);
+            if let Some(description) = distilled.get("description").and_then(Value::as_str) {
+                notes.extend(core::description_notes(description).map(str::to_string));
+            }
+            entry.insert(format!("{}_bodies", label), Value::from(count));
+            entry.insert(format!("{}_structures", label), catalog);
+            if let Some(lengths) = distilled.get("string_lengths") {
+                entry.insert(format!("{}_string_lengths", label), lengths.clone());
+            }
+            entry.insert(
+                format!("distilled_{}s", label),
+                distilled.get("distilled_data").cloned().unwrap_or(Value::Null),
+            );
+        }
+        distilled_endpoints.push(Value::Object(entry));
+    }
+
+    let mut description = format!(
+        "Per-endpoint structure catalog of a HAR capture ({} endpoints). Requests are grouped by \
+method and URL template (numeric ids, UUIDs, hashes, dates and long tokens in paths become \
+placeholders such as {{id}}). For each endpoint, the JSON response bodies (and request bodies, if any) \
+are distilled together as one list: *_structures lists every structure hash with its signature and \
+count, distilled_* shows examples. Non-JSON bodies are skipped.",
+        distilled_endpoints.len()
+    );
+    for note in &notes {
+        description.push('\n');
+        description.push_str(note);
+    }
+
+    let mut output = Map::new();
+    output.insert("description".to_string(), Value::String(description));
+    output.insert("hash_scheme".to_string(), Value::String(options.hash_scheme.to_string()));
+    if options.redaction.is_some() {
+        output.insert("redacted_values".to_string(), Value::from(redacted_values));
+    }
+    if !collisions.is_empty() {
+        output.insert("hash_collisions".to_string(), Value::Array(collisions));
+    }
+    output.insert("endpoints".to_string(), Value::Array(distilled_endpoints));
+    Ok(Value::Object(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ignore::IgnoreOptions;
+    use crate::redact::RedactionOptions;
+
+    fn entry(method: &str, url: &str, status: u16, body: Value) -> Value {
+        json!({
+            "request": { "method": method, "url": url },
+            "response": {
+                "status": status,
+                "content": { "mimeType": "application/json", "text": body.to_string() },
+            },
+        })
+    }
+
+    fn har(entries: Vec<Value>) -> Value {
+        json!({ "log": { "entries": entries } })
+    }
+
+    #[test]
+    fn url_templates_name_each_parameter() {
+        assert_eq!(url_template("/users/42/posts/7"), "/users/{id}/posts/{id2}");
+        assert_eq!(url_template("/orders/123e4567-e89b-12d3-a456-426614174000"), "/orders/{uuid}");
+        assert_eq!(url_template("/reports/2024-01-31/summary"), "/reports/{date}/summary");
+        assert_eq!(url_template("/blobs/9f86d081884c7d659a2feaa0c55ad015"), "/blobs/{hash}");
+        assert_eq!(url_template("/v2/users/me"), "/v2/users/me");
+    }
+
+    #[test]
+    fn entries_are_grouped_by_method_origin_and_template() {
+        let capture = har(vec![
+            entry("get", "https://api.example.com/users/1?expand=true", 200, json!({ "id": 1 })),
+            entry("GET", "https://api.example.com/users/2", 404, json!({ "error": "missing" })),
+            entry("POST", "https://api.example.com/users", 201, json!({ "id": 3 })),
+            json!({ "request": { "method": "GET", "url": "https://cdn.example.com/users/9" },
+                    "response": { "status": 200, "content": { "mimeType": "text/html", "text": "<p>" } } }),
+        ]);
+        let endpoints = extract_endpoints([&capture]).unwrap();
+        let names: Vec<String> = endpoints.iter().map(HarEndpoint::name).collect();
+        assert_eq!(
+            names,
+            [
+                "GET https://api.example.com/users/{id}",
+                "POST https://api.example.com/users",
+                "GET https://cdn.example.com/users/{id}",
+            ]
+        );
+        assert_eq!(endpoints[0].requests, 2);
+        assert_eq!(endpoints[0].statuses.iter().collect::<Vec<_>>(), [(&200, &1), (&404, &1)]);
+        assert_eq!(endpoints[0].example_url, "https://api.example.com/users/1?expand=true");
+        // HTML bodies are counted but not collected
+        assert_eq!(endpoints[2].requests, 1);
+        assert!(endpoints[2].response_bodies.is_empty());
+    }
+
+    #[test]
+    fn example_urls_are_redacted_with_the_bodies() {
+        let url = "https://api.example.com/users/1?token=abc123&email=a@b.com&page=2&to=x%40y.io#access_token=xyz";
+        let capture = har(vec![entry("GET", url, 200, json!({ "id": 1 }))]);
+
+        let plain = distill_har([&capture], &DistillOptions::default(), &DistillControl::default()).unwrap();
+        assert_eq!(plain["endpoints"][0]["example_url"], url);
+
+        let options = DistillOptions { redaction: Some(RedactionOptions::default()), ..DistillOptions::default() };
+        let output = distill_har([&capture], &options, &DistillControl::default()).unwrap();
+        assert_eq!(
+            output["endpoints"][0]["example_url"],
+            "https://api.example.com/users/1?token=<str>&email=<email>&page=2&to=<email>#access_token=<str>"
+        );
+        assert_eq!(output["redacted_values"], 4);
+    }
+
+    #[test]
+    fn base64_bodies_are_decoded() {
+        let text = base64::engine::general_purpose::STANDARD.encode(r#"{"ok": true}"#);
+        let content = json!({ "mimeType": "application/json", "encoding": "base64", "text": text });
+        assert_eq!(json_body(&content), Some(json!({ "ok": true })));
+        assert_eq!(json_body(&json!({ "mimeType": "text/plain", "text": "ok" })), None);
+    }
+
+    #[test]
+    fn endpoint_notes_reach_the_top_level_description() {
+        let capture = har(vec![
+            entry("GET", "https://api.example.com/users/1", 200, json!({ "user": { "profile": { "name": "Ann" } }, "_debug": 1 })),
+            entry("GET", "https://api.example.com/teams/1", 200, json!({ "team": { "owner": { "name": "Bob" } } })),
+        ]);
+        let options = DistillOptions {
+            max_depth: Some(1),
+            ignore: Some(IgnoreOptions::new(vec!["_debug".to_string()], Vec::new(), false)),
+            redaction: Some(RedactionOptions::default()),
+            ..DistillOptions::default()
+        };
+        let output = distill_har([&capture], &options, &DistillControl::default()).unwrap();
+        let description = output["description"].as_str().unwrap();
+
+        for note in ["more than 1 levels deep are replaced by stubs", "Ignored for structure detection", "Sensitive example values are redacted"] {
+            assert_eq!(description.matches(note).count(), 1, "{}: {}", note, description);
+        }
+        assert!(!description.contains("POSITION_DEPENDENT"));
+        assert!(output["endpoints"][0]["distilled_responses"].to_string().contains(r#""_stub":"object""#));
+    }
+}
@@ -0,0 +1,198 @@
+// src/ignore.rs - Leaving noisy object members out of structure detection
+
+use serde_json::{Map, Value};
+
+/// Rest of an ignore path still to be matched below an object member
+pub(crate) type PathTail<'a> = &'a [String];
+
+/// Object members left out of structure keys, so fields that vary for uninteresting
+/// reasons (`_debug`, `extensions`, `metadata.trace`) don't split otherwise identical
+/// structures. Patterns may use `*` as a wildcard.
+#[derive(Debug, Clone)]
+pub struct IgnoreOptions {
+    /// Key-name patterns, matched at any depth
+    pub keys: Vec<String>,
+    /// Dotted key paths such as `metadata.trace`. A path may start at any object, but
+    /// doesn't cross lists (start it inside the list items instead), so list items get
+    /// the same structure whether they are hashed on their own or as part of a parent
+    pub paths: Vec<String>,
+    /// Also remove ignored members from the examples (and every other output)
+    pub from_examples: bool,
+    /// `paths` split into segments
+    path_segments: Vec<Vec<String>>,
+}
+
+impl IgnoreOptions {
+    pub fn new(keys: Vec<String>, paths: Vec<String>, from_examples: bool) -> Self {
+        let path_segments = paths
+            .iter()
+            .map(|path| path.split('.').map(str::to_string).collect())
+            .collect();
+        Self { keys, paths, from_examples, path_segments }
+    }
+
+    /// Whether member `key` of an object is ignored, given the path tails that reached the
+    /// object. `None` means ignored; otherwise the tails that continue into the member's value
+    pub(crate) fn member<'a>(&'a self, tails: &[PathTail<'a>], key: &str) -> Option<Vec<PathTail<'a>>> {
+        if self.keys.iter().any(|pattern| wildcard_match(pattern, key)) {
+            return None;
+        }
+        let mut next = Vec::new();
+        for path in self.path_segments.iter().map(Vec::as_slice).chain(tails.iter().copied()) {
+            if let Some((head, rest)) = path.split_first() {
+                if wildcard_match(head, key) {
+                    if rest.is_empty() {
+                        return None;
+                    }
+                    next.push(rest);
+                }
+            }
+        }
+        Some(next)
+    }
+
+    /// Copy of a document without the ignored members
+    pub fn pruned(&self, value: &Value) -> Value {
+        let mut value = value.clone();
+        self.prune(&mut value);
+        value
+    }
+
+    /// Remove the ignored members from a document
+    pub fn prune(&self, value: &mut Value) {
+        self.prune_in(value, &[]);
+    }
+
+    fn prune_in(&self, value: &mut Value, tails: &[PathTail<'_>]) {
+        match value {
+            Value::Object(map) => {
+                let mut kept = Map::with_capacity(map.len());
+                for (k, mut v) in std::mem::take(map) {
+                    if let Some(tails) = self.member(tails, &k) {
+                        self.prune_in(&mut v, &tails);
+                        kept.insert(k, v);
+                    }
+                }
+                *map = kept;
+            }
+            Value::Array(list) => {
+                for item in list {
+                    self.prune_in(item, &[]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// "keys _debug, extensions; paths metadata.trace"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.keys.is_empty() {
+            parts.push(format!("keys {}", self.keys.join(", ")));
+        }
+        if !self.paths.is_empty() {
+            parts.push(format!("paths {}", self.paths.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Match `text` against a pattern where `*` stands for any run of characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let Some((first, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(mut remaining) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut pieces: Vec<&str> = rest.split('*').collect();
+    let last = pieces.pop().unwrap_or_default();
+    for piece in pieces {
+        match remaining.find(piece) {
+            Some(i) => remaining = &remaining[i + piece.len()..],
+            None => return false,
+        }
+    }
+    remaining.len() >= last.len() && remaining.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{distill_json_with_options, DistillControl, DistillOptions};
+    use serde_json::json;
+
+    fn ignoring(keys: &[&str], paths: &[&str]) -> IgnoreOptions {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        IgnoreOptions::new(strings(keys), strings(paths), false)
+    }
+
+    #[test]
+    fn wildcards_match_any_run_of_characters() {
+        assert!(wildcard_match("_debug", "_debug"));
+        assert!(!wildcard_match("_debug", "_debugger"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("x-*", "x-request-id"));
+        assert!(wildcard_match("*_at", "created_at"));
+        assert!(wildcard_match("a*b*c", "a-b-b-c"));
+        assert!(!wildcard_match("a*b*c", "a-c-b"));
+        // The prefix and suffix can't overlap
+        assert!(!wildcard_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn keys_are_ignored_at_any_depth_and_paths_from_any_object() {
+        let ignore = ignoring(&["_*"], &["metadata.trace"]);
+        assert!(ignore.member(&[], "_debug").is_none());
+        assert!(ignore.member(&[], "metadata").is_some_and(|tails| tails.len() == 1));
+
+        let tails = ignore.member(&[], "metadata").unwrap();
+        assert!(ignore.member(&tails, "trace").is_none());
+        assert!(ignore.member(&tails, "other").is_some_and(|tails| tails.is_empty()));
+        // Without having come through `metadata`, `trace` is kept
+        assert!(ignore.member(&[], "trace").is_some());
+    }
+
+    #[test]
+    fn pruning_removes_ignored_members_but_paths_stop_at_lists() {
+        let ignore = ignoring(&["_debug"], &["meta.trace", "items.id"]);
+        let data = json!({
+            "_debug": 1,
+            "meta": { "trace": "t", "keep": [{ "_debug": 2, "trace": "kept" }] },
+            "items": [{ "id": 1 }],
+            "wrap": { "meta": { "trace": "t", "n": 1 } }
+        });
+        assert_eq!(
+            ignore.pruned(&data),
+            json!({
+                "meta": { "keep": [{ "trace": "kept" }] },
+                "items": [{ "id": 1 }],
+                "wrap": { "meta": { "n": 1 } }
+            })
+        );
+    }
+
+    #[test]
+    fn ignored_members_dont_split_structures() {
+        let data = json!({ "users": [{ "id": 1, "_debug": { "ms": 3 } }, { "id": 2 }, { "id": 3, "_debug": null }] });
+        let items = |ignore: Option<IgnoreOptions>| {
+            let options = DistillOptions { ignore, ..DistillOptions::default() };
+            let output = distill_json_with_options(data.clone(), &options, &DistillControl::default()).unwrap();
+            output["distilled_data"]["users"].as_array().unwrap().clone()
+        };
+        assert_eq!(items(None).len(), 3);
+
+        let users = items(Some(ignoring(&["_debug"], &[])));
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0]["_debug"], json!({ "ms": 3 }));
+        assert_eq!(users[1]["item_count"], 2);
+        let hash = users[0]["_structure_hash"].as_str().unwrap();
+        assert!(users[1]["summarized_pattern"].as_str().unwrap().starts_with(hash));
+    }
+
+    #[test]
+    fn describe_lists_keys_and_paths() {
+        assert_eq!(ignoring(&["_debug", "x-*"], &["meta.trace"]).describe(), "keys _debug, x-*; paths meta.trace");
+        assert_eq!(ignoring(&[], &["meta.trace"]).describe(), "paths meta.trace");
+    }
+}
@@ -0,0 +1,916 @@
+// src/input.rs - Input formats converted to serde_json::Value before distillation
+
+use crate::error::{DistillError, Result};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Map, Number, Value};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Single-key wrapper objects used for primitives that JSON can't express
+/// (`{"$oid": "..."}`, `{"$int32": 5}`), and the primitive type name each one gets in
+/// structure keys when typed primitives are enabled. Binary decoders emit the `$`-prefixed
+/// names; MongoDB Extended JSON spellings are accepted so mongoexport output works too.
+const TYPED_PRIMITIVES: &[(&str, &str)] = &[
+    ("$oid", "ObjectId"),
+    ("$date", "datetime"),
+    ("$binary", "bytes"),
+    ("$int32", "int32"),
+    ("$int64", "int64"),
+    ("$float32", "float32"),
+    ("$bigint", "bigint"),
+    ("$decimal128", "Decimal128"),
+    ("$timestamp", "Timestamp"),
+    ("$regex", "Regex"),
+    ("$code", "Code"),
+    ("$symbol", "Symbol"),
+    ("$dbPointer", "DBPointer"),
+    ("$minKey", "MinKey"),
+    ("$maxKey", "MaxKey"),
+    ("$undefined", "undefined"),
+    ("$ext", "ext"),
+    // MongoDB Extended JSON (canonical mode)
+    ("$numberInt", "int32"),
+    ("$numberLong", "int64"),
+    ("$numberDouble", "float"),
+    ("$numberDecimal", "Decimal128"),
+];
+
+/// Primitive type name of a typed-primitive wrapper object, if `map` is one
+#[inline]
+pub(crate) fn typed_primitive_name(map: &Map<String, Value>) -> Option<&'static str> {
+    if map.len() != 1 {
+        return None;
+    }
+    let key = map.keys().next()?;
+    if !key.starts_with('$') {
+        return None;
+    }
+    TYPED_PRIMITIVES
+        .iter()
+        .find(|(tag, _)| tag == key)
+        .map(|(_, name)| *name)
+}
+
+/// Supported input formats. Every format is converted to a `serde_json::Value` with
+/// object keys in document order, so structure hashes behave the same as for JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputFormat {
+    #[default]
+    Json,
+    /// Newline-delimited JSON (one document per line), distilled as one list
+    Ndjson,
+    /// YAML; multi-document streams (`---`) are distilled as one list
+    Yaml,
+    Toml,
+    Json5,
+    /// JSON with comments and trailing commas (parsed with the JSON5 parser)
+    Jsonc,
+    /// MessagePack; a stream of concatenated values is distilled as one list
+    MessagePack,
+    /// CBOR; a stream of concatenated items is distilled as one list
+    Cbor,
+    /// BSON; a dump of concatenated documents (mongodump) is distilled as one list
+    Bson,
+}
+
+impl InputFormat {
+    /// Guess the format from a file extension, defaulting to JSON.
+    /// A compression extension is skipped, so `events.ndjson.zst` is NDJSON.
+    pub fn from_path(path: &Path) -> Self {
+        Self::from_known_extension(path).unwrap_or_default()
+    }
+
+    /// The format named by a file extension, if it is one this crate reads
+    /// (`.json` included), skipping a compression extension
+    pub fn from_known_extension(path: &Path) -> Option<Self> {
+        let path = match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if Compression::from_extension(e).is_some() => Path::new(path.file_stem().unwrap_or_default()),
+            _ => path,
+        };
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("json") | Some("har") => Some(InputFormat::Json),
+            Some("yaml") | Some("yml") => Some(InputFormat::Yaml),
+            Some("toml") => Some(InputFormat::Toml),
+            Some("json5") => Some(InputFormat::Json5),
+            Some("jsonc") => Some(InputFormat::Jsonc),
+            Some("ndjson") | Some("jsonl") => Some(InputFormat::Ndjson),
+            Some("msgpack") | Some("mpk") | Some("mp") => Some(InputFormat::MessagePack),
+            Some("cbor") => Some(InputFormat::Cbor),
+            Some("bson") => Some(InputFormat::Bson),
+            _ => None,
+        }
+    }
+
+    /// Binary formats carry types JSON can't express (see `TYPED_PRIMITIVES`)
+    pub fn is_binary(self) -> bool {
+        matches!(self, InputFormat::MessagePack | InputFormat::Cbor | InputFormat::Bson)
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputFormat::Json => "json",
+            InputFormat::Ndjson => "ndjson",
+            InputFormat::Yaml => "yaml",
+            InputFormat::Toml => "toml",
+            InputFormat::Json5 => "json5",
+            InputFormat::Jsonc => "jsonc",
+            InputFormat::MessagePack => "msgpack",
+            InputFormat::Cbor => "cbor",
+            InputFormat::Bson => "bson",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = DistillError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(InputFormat::Json),
+            "ndjson" | "jsonl" => Ok(InputFormat::Ndjson),
+            "yaml" | "yml" => Ok(InputFormat::Yaml),
+            "toml" => Ok(InputFormat::Toml),
+            "json5" => Ok(InputFormat::Json5),
+            "jsonc" => Ok(InputFormat::Jsonc),
+            "msgpack" | "messagepack" => Ok(InputFormat::MessagePack),
+            "cbor" => Ok(InputFormat::Cbor),
+            "bson" => Ok(InputFormat::Bson),
+            other => Err(DistillError::InvalidInput(format!(
+                "Unknown input format '{}' (expected json, ndjson, yaml, toml, json5, jsonc, msgpack, cbor or bson)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Compression of an input file, detected from its magic bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Detect compression from the first bytes of a file
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if header.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else {
+            Compression::None
+        }
+    }
+
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Open a file for reading, transparently decompressing gzip, zstd and bzip2 (detected
+/// from magic bytes, not the extension). Decompression streams, so NDJSON input is never
+/// fully decompressed in memory.
+pub fn open_input(path: &Path) -> Result<(Box<dyn BufRead + Send>, Compression)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let compression = Compression::detect(reader.fill_buf()?);
+    let reader: Box<dyn BufRead + Send> = match compression {
+        Compression::None => Box::new(reader),
+        // Multi-member decoders, so concatenated archives (`cat a.gz b.gz`) read fully
+        Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::stream::read::Decoder::with_buffer(reader)?)),
+        Compression::Bzip2 => Box::new(BufReader::new(bzip2::bufread::MultiBzDecoder::new(reader))),
+    };
+    Ok((reader, compression))
+}
+
+/// Read and parse a file in the given format, decompressing it if needed.
+/// `typed_primitives` controls how binary formats represent types JSON lacks: as
+/// `$`-tagged wrapper objects, or as plain JSON numbers and strings.
+pub fn read_input(path: &Path, format: InputFormat, typed_primitives: bool) -> Result<Value> {
+    let (reader, _) = open_input(path)?;
+    parse_reader(reader, format, typed_primitives)
+}
+
+/// Parse a document from a reader. NDJSON is parsed line by line; other formats
+/// need the whole document in memory.
+pub fn parse_reader<R: BufRead>(mut reader: R, format: InputFormat, typed_primitives: bool) -> Result<Value> {
+    if format == InputFormat::Ndjson {
+        return parse_ndjson(reader);
+    }
+    if format.is_binary() {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        return parse_bytes(&bytes, format, typed_primitives);
+    }
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    parse_str(&content, format)
+}
+
+/// Parse an in-memory binary document
+pub fn parse_bytes(bytes: &[u8], format: InputFormat, typed_primitives: bool) -> Result<Value> {
+    let documents = match format {
+        InputFormat::MessagePack => {
+            let mut decoder = MsgpackDecoder { bytes, pos: 0, typed: typed_primitives, depth: 0 };
+            let mut documents = Vec::new();
+            while decoder.pos < bytes.len() {
+                documents.push(decoder.read_value()?);
+            }
+            documents
+        }
+        InputFormat::Cbor => {
+            let mut reader = bytes;
+            let mut documents = Vec::new();
+            while !reader.is_empty() {
+                let value: ciborium::Value = ciborium::de::from_reader(&mut reader)
+                    .map_err(|e| DistillError::BinaryDecode(format!("CBOR: {}", e)))?;
+                documents.push(cbor_to_json(value, typed_primitives));
+            }
+            documents
+        }
+        InputFormat::Bson => {
+            let mut reader = bytes;
+            let mut documents = Vec::new();
+            while !reader.is_empty() {
+                let document = bson::Document::from_reader(&mut reader)
+                    .map_err(|e| DistillError::BinaryDecode(format!("BSON: {}", e)))?;
+                documents.push(bson_document_to_json(document, typed_primitives));
+            }
+            documents
+        }
+        _ => {
+            let content = std::str::from_utf8(bytes)
+                .map_err(|e| DistillError::InvalidInput(format!("{} input is not valid UTF-8: {}", format, e)))?;
+            return parse_str(content, format);
+        }
+    };
+    Ok(single_or_list(documents))
+}
+
+/// One document is returned as-is; a stream of several becomes a list
+fn single_or_list(mut documents: Vec<Value>) -> Value {
+    match documents.len() {
+        0 => Value::Null,
+        1 => documents.swap_remove(0),
+        _ => Value::Array(documents),
+    }
+}
+
+/// Parse an in-memory text document
+pub fn parse_str(content: &str, format: InputFormat) -> Result<Value> {
+    match format {
+        InputFormat::Json => Ok(serde_json::from_str(content)?),
+        InputFormat::Ndjson => parse_ndjson(content.as_bytes()),
+        InputFormat::Yaml => parse_yaml(content),
+        InputFormat::Toml => {
+            let table: toml::Table = content.parse()?;
+            Ok(toml_to_json(toml::Value::Table(table)))
+        }
+        InputFormat::Json5 | InputFormat::Jsonc => Ok(json5::from_str(content)?),
+        InputFormat::MessagePack | InputFormat::Cbor | InputFormat::Bson => Err(DistillError::InvalidInput(
+            format!("{} is a binary format and can't be read from text", format),
+        )),
+    }
+}
+
+fn parse_ndjson<R: BufRead>(reader: R) -> Result<Value> {
+    let mut documents = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let document: Value = serde_json::from_str(&line).map_err(|e| {
+            DistillError::InvalidInput(format!("NDJSON line {}: {}", index + 1, e))
+        })?;
+        documents.push(document);
+    }
+    Ok(Value::Array(documents))
+}
+
+/// A single YAML document is returned as-is; a multi-document stream becomes a list
+fn parse_yaml(content: &str) -> Result<Value> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let mut value = serde_yaml::Value::deserialize(document)?;
+        // Resolve `<<: *anchor` merge keys (Compose files, Helm values)
+        value.apply_merge()?;
+        documents.push(yaml_to_json(value));
+    }
+    // Trailing `---` separators produce empty documents
+    if documents.len() > 1 {
+        documents.retain(|d| !d.is_null());
+    }
+    Ok(single_or_list(documents))
+}
+
+/// Non-finite floats have no JSON representation; keep them as their text form
+fn float_to_json(f: f64) -> Value {
+    Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(f.to_string()))
+}
+
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                float_to_json(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(items) => Value::Array(items.into_iter().map(yaml_to_json).collect()),
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut map = Map::with_capacity(mapping.len());
+            for (k, v) in mapping {
+                map.insert(yaml_key(k), yaml_to_json(v));
+            }
+            Value::Object(map)
+        }
+        // Custom tags (`!Ref`, `!!binary`) don't change structure; keep the tagged value
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+/// YAML allows non-string keys; JSON objects need strings
+fn yaml_key(key: serde_yaml::Value) -> String {
+    match key {
+        serde_yaml::Value::String(s) => s,
+        serde_yaml::Value::Null => "null".to_string(),
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        serde_yaml::Value::Number(n) => n.to_string(),
+        other => serde_yaml::to_string(&other)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => float_to_json(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        // RFC 3339 text, as JSON APIs usually carry timestamps
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => {
+            let mut map = Map::with_capacity(table.len());
+            for (k, v) in table {
+                map.insert(k, toml_to_json(v));
+            }
+            Value::Object(map)
+        }
+    }
+}
+
+/// Wrap a primitive in its typed-primitive object, or keep the plain JSON value
+fn typed(tag: &str, value: Value, typed_primitives: bool) -> Value {
+    if typed_primitives {
+        let mut map = Map::with_capacity(1);
+        map.insert(tag.to_string(), value);
+        Value::Object(map)
+    } else {
+        value
+    }
+}
+
+fn base64_string(bytes: &[u8]) -> Value {
+    Value::String(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// RFC 3339 text for a Unix timestamp, falling back to the raw seconds if out of range
+fn timestamp_string(seconds: i64, nanos: u32) -> Value {
+    match chrono::DateTime::from_timestamp(seconds, nanos) {
+        Some(dt) => Value::String(dt.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)),
+        None => Value::from(seconds),
+    }
+}
+
+/// Map keys must be strings in JSON; other keys use their JSON text
+fn key_string(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+/// Deepest nesting of MessagePack arrays and maps, the same limit serde_json applies
+const MSGPACK_MAX_DEPTH: usize = 128;
+
+/// MessagePack decoder working at the marker level, so binary, timestamps and float32
+/// survive (rmp-serde would turn them into arrays and f64)
+struct MsgpackDecoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    typed: bool,
+    /// Arrays and maps currently open
+    depth: usize,
+}
+
+impl<'a> MsgpackDecoder<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(n).filter(|end| *end <= self.bytes.len()).ok_or_else(|| {
+            DistillError::BinaryDecode(format!("MessagePack: unexpected end of input at byte {}", self.pos))
+        })?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_be(&mut self, n: usize) -> Result<u64> {
+        Ok(self.take(n)?.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b)))
+    }
+
+    /// Arrays and maps are read here; everything else in `read_scalar`, so the frames of
+    /// this recursion stay small even in debug builds
+    fn read_value(&mut self) -> Result<Value> {
+        use rmp::Marker;
+
+        match Marker::from_u8(self.take(1)?[0]) {
+            Marker::FixArray(len) => self.read_array(usize::from(len)),
+            Marker::Array16 => { let len = self.read_be(2)? as usize; self.read_array(len) }
+            Marker::Array32 => { let len = self.read_be(4)? as usize; self.read_array(len) }
+            Marker::FixMap(len) => self.read_map(usize::from(len)),
+            Marker::Map16 => { let len = self.read_be(2)? as usize; self.read_map(len) }
+            Marker::Map32 => { let len = self.read_be(4)? as usize; self.read_map(len) }
+            marker => self.read_scalar(marker),
+        }
+    }
+
+    #[inline(never)]
+    fn read_scalar(&mut self, marker: rmp::Marker) -> Result<Value> {
+        use rmp::Marker;
+
+        let t = self.typed;
+        // Encoders pick the smallest marker that fits each value, so the width says nothing
+        // about the field: integers are plain numbers, as in CBOR and JSON input
+        let value = match marker {
+            Marker::Null => Value::Null,
+            Marker::True => Value::Bool(true),
+            Marker::False => Value::Bool(false),
+            Marker::FixPos(n) => Value::from(n),
+            Marker::FixNeg(n) => Value::from(n),
+            Marker::U8 => { let n = self.read_be(1)?; Value::from(n) }
+            Marker::U16 => { let n = self.read_be(2)?; Value::from(n) }
+            Marker::U32 => { let n = self.read_be(4)?; Value::from(n) }
+            Marker::U64 => { let n = self.read_be(8)?; Value::from(n) }
+            Marker::I8 => { let n = self.read_be(1)? as u8 as i8; Value::from(n) }
+            Marker::I16 => { let n = self.read_be(2)? as u16 as i16; Value::from(n) }
+            Marker::I32 => { let n = self.read_be(4)? as u32 as i32; Value::from(n) }
+            Marker::I64 => { let n = self.read_be(8)? as i64; Value::from(n) }
+            Marker::F32 => {
+                let f = f32::from_bits(self.read_be(4)? as u32);
+                typed("$float32", float_to_json(f64::from(f)), t)
+            }
+            Marker::F64 => float_to_json(f64::from_bits(self.read_be(8)?)),
+            Marker::FixStr(len) => self.read_str(usize::from(len))?,
+            Marker::Str8 => { let len = self.read_be(1)? as usize; self.read_str(len)? }
+            Marker::Str16 => { let len = self.read_be(2)? as usize; self.read_str(len)? }
+            Marker::Str32 => { let len = self.read_be(4)? as usize; self.read_str(len)? }
+            Marker::Bin8 => { let len = self.read_be(1)? as usize; self.read_bin(len)? }
+            Marker::Bin16 => { let len = self.read_be(2)? as usize; self.read_bin(len)? }
+            Marker::Bin32 => { let len = self.read_be(4)? as usize; self.read_bin(len)? }
+            Marker::FixExt1 => self.read_ext(1)?,
+            Marker::FixExt2 => self.read_ext(2)?,
+            Marker::FixExt4 => self.read_ext(4)?,
+            Marker::FixExt8 => self.read_ext(8)?,
+            Marker::FixExt16 => self.read_ext(16)?,
+            Marker::Ext8 => { let len = self.read_be(1)? as usize; self.read_ext(len)? }
+            Marker::Ext16 => { let len = self.read_be(2)? as usize; self.read_ext(len)? }
+            Marker::Ext32 => { let len = self.read_be(4)? as usize; self.read_ext(len)? }
+            Marker::Reserved => {
+                return Err(DistillError::BinaryDecode(format!(
+                    "MessagePack: reserved marker 0xc1 at byte {}",
+                    self.pos - 1
+                )))
+            }
+            Marker::FixArray(_) | Marker::Array16 | Marker::Array32 | Marker::FixMap(_) | Marker::Map16 | Marker::Map32 => {
+                return Err(DistillError::Internal("MessagePack container marker in read_scalar".to_string()))
+            }
+        };
+        Ok(value)
+    }
+
+    fn read_str(&mut self, len: usize) -> Result<Value> {
+        let bytes = self.take(len)?;
+        Ok(Value::String(String::from_utf8_lossy(bytes).into_owned()))
+    }
+
+    fn read_bin(&mut self, len: usize) -> Result<Value> {
+        let bytes = self.take(len)?;
+        Ok(typed("$binary", base64_string(bytes), self.typed))
+    }
+
+    /// Open an array or map, failing on input nested deeper than `MSGPACK_MAX_DEPTH`
+    fn enter(&mut self) -> Result<()> {
+        if self.depth == MSGPACK_MAX_DEPTH {
+            return Err(DistillError::BinaryDecode(format!(
+                "MessagePack: nested deeper than {} levels at byte {}",
+                MSGPACK_MAX_DEPTH,
+                self.pos - 1
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn read_array(&mut self, len: usize) -> Result<Value> {
+        self.enter()?;
+        // Don't trust the declared length for preallocation
+        let mut items = Vec::with_capacity(len.min(1024));
+        for _ in 0..len {
+            items.push(self.read_value()?);
+        }
+        self.depth -= 1;
+        Ok(Value::Array(items))
+    }
+
+    fn read_map(&mut self, len: usize) -> Result<Value> {
+        self.enter()?;
+        let mut map = Map::with_capacity(len.min(1024));
+        for _ in 0..len {
+            // Typed wrappers would make poor keys; decode keys as plain values
+            let typed = std::mem::replace(&mut self.typed, false);
+            let key = self.read_value();
+            self.typed = typed;
+            let value = self.read_value()?;
+            map.insert(key_string(key?), value);
+        }
+        self.depth -= 1;
+        Ok(Value::Object(map))
+    }
+
+    fn read_ext(&mut self, len: usize) -> Result<Value> {
+        let ext_type = self.take(1)?[0] as i8;
+        let data = self.take(len)?;
+        // Type -1 is the MessagePack timestamp extension (32, 64 or 96 bit)
+        if ext_type == -1 {
+            let (seconds, nanos) = match data.len() {
+                4 => (i64::from(u32::from_be_bytes([data[0], data[1], data[2], data[3]])), 0),
+                8 => {
+                    let raw = u64::from_be_bytes(data.try_into().unwrap_or_default());
+                    ((raw & 0x3_ffff_ffff) as i64, (raw >> 34) as u32)
+                }
+                12 => {
+                    let nanos = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+                    let seconds = i64::from_be_bytes(data[4..12].try_into().unwrap_or_default());
+                    (seconds, nanos)
+                }
+                _ => {
+                    return Err(DistillError::BinaryDecode(format!(
+                        "MessagePack: invalid timestamp length {}",
+                        data.len()
+                    )))
+                }
+            };
+            return Ok(typed("$date", timestamp_string(seconds, nanos), self.typed));
+        }
+        Ok(typed("$ext", json!({ "type": ext_type, "data": base64_string(data) }), self.typed))
+    }
+}
+
+fn cbor_to_json(value: ciborium::Value, typed_primitives: bool) -> Value {
+    use ciborium::Value as Cbor;
+
+    match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Integer(i) => {
+            let i = i128::from(i);
+            i64::try_from(i)
+                .map(Value::from)
+                .or_else(|_| u64::try_from(i).map(Value::from))
+                .unwrap_or_else(|_| typed("$bigint", Value::String(i.to_string()), typed_primitives))
+        }
+        Cbor::Float(f) => float_to_json(f),
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Bytes(bytes) => typed("$binary", base64_string(&bytes), typed_primitives),
+        Cbor::Array(items) => Value::Array(items.into_iter().map(|v| cbor_to_json(v, typed_primitives)).collect()),
+        Cbor::Map(entries) => {
+            let mut map = Map::with_capacity(entries.len());
+            for (k, v) in entries {
+                map.insert(key_string(cbor_to_json(k, false)), cbor_to_json(v, typed_primitives));
+            }
+            Value::Object(map)
+        }
+        Cbor::Tag(tag, inner) => match (tag, *inner) {
+            // Tag 0: RFC 3339 date/time string
+            (0, Cbor::Text(s)) => typed("$date", Value::String(s), typed_primitives),
+            // Tag 1: epoch-based date/time
+            (1, Cbor::Integer(i)) => {
+                let seconds = i64::try_from(i128::from(i)).unwrap_or_default();
+                typed("$date", timestamp_string(seconds, 0), typed_primitives)
+            }
+            (1, Cbor::Float(f)) => {
+                let seconds = f.floor();
+                let nanos = ((f - seconds) * 1e9) as u32;
+                typed("$date", timestamp_string(seconds as i64, nanos), typed_primitives)
+            }
+            // Tags 2/3: bignums, kept as hex
+            (2 | 3, Cbor::Bytes(bytes)) => {
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                let sign = if tag == 3 { "-1-" } else { "" };
+                typed("$bigint", Value::String(format!("{}0x{}", sign, hex)), typed_primitives)
+            }
+            // Other tags (URIs, regexes, ...) don't change structure; keep the tagged value
+            (_, inner) => cbor_to_json(inner, typed_primitives),
+        },
+        _ => Value::Null,
+    }
+}
+
+fn bson_document_to_json(document: bson::Document, typed_primitives: bool) -> Value {
+    let mut map = Map::with_capacity(document.len());
+    for (k, v) in document {
+        map.insert(k, bson_to_json(v, typed_primitives));
+    }
+    Value::Object(map)
+}
+
+fn bson_to_json(value: bson::Bson, typed_primitives: bool) -> Value {
+    use bson::Bson;
+
+    let t = typed_primitives;
+    match value {
+        Bson::Double(f) => float_to_json(f),
+        Bson::String(s) => Value::String(s),
+        Bson::Array(items) => Value::Array(items.into_iter().map(|v| bson_to_json(v, t)).collect()),
+        Bson::Document(document) => bson_document_to_json(document, t),
+        Bson::Boolean(b) => Value::Bool(b),
+        Bson::Null => Value::Null,
+        Bson::RegularExpression(regex) => {
+            typed("$regex", Value::String(format!("/{}/{}", regex.pattern, regex.options)), t)
+        }
+        Bson::JavaScriptCode(code) => typed("$code", Value::String(code), t),
+        Bson::JavaScriptCodeWithScope(code) => typed("$code", Value::String(code.code), t),
+        Bson::Int32(i) => typed("$int32", Value::from(i), t),
+        Bson::Int64(i) => typed("$int64", Value::from(i), t),
+        Bson::Timestamp(ts) => typed("$timestamp", json!({ "t": ts.time, "i": ts.increment }), t),
+        Bson::Binary(binary) => typed("$binary", base64_string(&binary.bytes), t),
+        Bson::ObjectId(oid) => typed("$oid", Value::String(oid.to_hex()), t),
+        Bson::DateTime(dt) => {
+            let text = dt
+                .try_to_rfc3339_string()
+                .map(Value::String)
+                .unwrap_or_else(|_| Value::from(dt.timestamp_millis()));
+            typed("$date", text, t)
+        }
+        Bson::Symbol(s) => typed("$symbol", Value::String(s), t),
+        Bson::Decimal128(d) => typed("$decimal128", Value::String(d.to_string()), t),
+        Bson::Undefined => typed("$undefined", Value::Bool(true), t),
+        Bson::MaxKey => typed("$maxKey", Value::from(1), t),
+        Bson::MinKey => typed("$minKey", Value::from(1), t),
+        Bson::DbPointer(_) => typed("$dbPointer", Value::Null, t),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(value: &Value) -> Vec<&str> {
+        value.as_object().unwrap().keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn text_formats_keep_key_order() {
+        let yaml = parse_str("zeta: 1\nalpha: [a, b]\nmid: {y: 1, x: 2}\n", InputFormat::Yaml).unwrap();
+        assert_eq!(keys(&yaml), ["zeta", "alpha", "mid"]);
+        assert_eq!(keys(&yaml["mid"]), ["y", "x"]);
+
+        let toml = parse_str("zeta = 1\nalpha = \"a\"\n[mid]\ny = 1\nx = 2.5\n", InputFormat::Toml).unwrap();
+        assert_eq!(toml, json!({ "zeta": 1, "alpha": "a", "mid": { "y": 1, "x": 2.5 } }));
+        assert_eq!(keys(&toml), ["zeta", "alpha", "mid"]);
+
+        let json5 = parse_str("{zeta: 1, alpha: 'a', mid: {y: 1, x: 2,},}", InputFormat::Json5).unwrap();
+        assert_eq!(keys(&json5), ["zeta", "alpha", "mid"]);
+    }
+
+    #[test]
+    fn jsonc_accepts_comments_and_trailing_commas() {
+        let content = "{\n  // editor settings\n  \"tabSize\": 4, /* spaces */\n  \"rulers\": [80, 120,],\n}";
+        assert_eq!(parse_str(content, InputFormat::Jsonc).unwrap(), json!({ "tabSize": 4, "rulers": [80, 120] }));
+    }
+
+    #[test]
+    fn yaml_streams_become_lists_and_merge_keys_resolve() {
+        let content = "base: &base {image: app, replicas: 1}\nweb:\n  <<: *base\n  replicas: 3\n";
+        let document = parse_str(content, InputFormat::Yaml).unwrap();
+        assert_eq!(document["web"], json!({ "replicas": 3, "image": "app" }));
+
+        let stream = parse_str("---\nkind: Service\n---\nkind: Deployment\n---\n", InputFormat::Yaml).unwrap();
+        assert_eq!(stream, json!([{ "kind": "Service" }, { "kind": "Deployment" }]));
+    }
+
+    #[test]
+    fn ndjson_skips_blank_lines_and_reports_bad_ones() {
+        let documents = parse_str("{\"id\": 1}\n\n{\"id\": 2}\n", InputFormat::Ndjson).unwrap();
+        assert_eq!(documents, json!([{ "id": 1 }, { "id": 2 }]));
+
+        let error = parse_str("{\"id\": 1}\n{\"id\": \n", InputFormat::Ndjson).unwrap_err();
+        assert!(error.to_string().contains("NDJSON line 2"), "{}", error);
+    }
+
+    #[test]
+    fn formats_follow_extensions_behind_compression() {
+        assert_eq!(InputFormat::from_path(Path::new("events.ndjson.zst")), InputFormat::Ndjson);
+        assert_eq!(InputFormat::from_path(Path::new("values.YML")), InputFormat::Yaml);
+        assert_eq!(InputFormat::from_path(Path::new("data.unknown")), InputFormat::Json);
+        assert_eq!(InputFormat::from_known_extension(Path::new("data.unknown")), None);
+        assert_eq!("jsonl".parse::<InputFormat>().unwrap(), InputFormat::Ndjson);
+    }
+
+    #[test]
+    fn msgpack_integers_ignore_the_encoded_width() {
+        // {"id": 5}, {"id": 300}, {"id": -1}, {"id": u64::MAX}: fixint, uint16, negative fixint, uint64
+        let mut bytes = vec![0x81, 0xa2, b'i', b'd', 0x05];
+        bytes.extend([0x81, 0xa2, b'i', b'd', 0xcd, 0x01, 0x2c]);
+        bytes.extend([0x81, 0xa2, b'i', b'd', 0xff]);
+        bytes.extend([
+            0x81, 0xa2, b'i', b'd', 0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        ]);
+
+        let expected = json!([{ "id": 5 }, { "id": 300 }, { "id": -1 }, { "id": u64::MAX }]);
+        assert_eq!(
+            parse_bytes(&bytes, InputFormat::MessagePack, true).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_bytes(&bytes, InputFormat::MessagePack, false).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn msgpack_and_cbor_integers_get_the_same_types() {
+        // {"id": 300, "big": u64::MAX, "neg": -1}
+        let mut msgpack = vec![0x83, 0xa2, b'i', b'd', 0xcd, 0x01, 0x2c];
+        msgpack.extend([
+            0xa3, b'b', b'i', b'g', 0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        ]);
+        msgpack.extend([0xa3, b'n', b'e', b'g', 0xff]);
+        let mut cbor = Vec::new();
+        let entries = vec![
+            ("id".into(), 300.into()),
+            ("big".into(), u64::MAX.into()),
+            ("neg".into(), (-1).into()),
+        ];
+        ciborium::ser::into_writer(&ciborium::Value::Map(entries), &mut cbor).unwrap();
+
+        let from_msgpack = parse_bytes(&msgpack, InputFormat::MessagePack, true).unwrap();
+        assert_eq!(
+            from_msgpack,
+            parse_bytes(&cbor, InputFormat::Cbor, true).unwrap()
+        );
+        assert_eq!(
+            from_msgpack,
+            json!({ "id": 300, "big": u64::MAX, "neg": -1 })
+        );
+    }
+
+    #[test]
+    fn msgpack_nesting_is_limited() {
+        let nested = |depth: usize| {
+            let mut bytes = vec![0x91; depth]; // one-element fixarrays
+            bytes.push(0xc0);
+            bytes
+        };
+        assert!(parse_bytes(&nested(MSGPACK_MAX_DEPTH), InputFormat::MessagePack, true).is_ok());
+        let error = parse_bytes(
+            &nested(MSGPACK_MAX_DEPTH + 1),
+            InputFormat::MessagePack,
+            true,
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("nested deeper than 128"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn msgpack_keeps_types_json_lacks() {
+        // [bin8 "hi", float32 1.5, timestamp32 0]
+        let bytes = [0x93, 0xc4, 0x02, b'h', b'i', 0xca, 0x3f, 0xc0, 0x00, 0x00, 0xd6, 0xff, 0, 0, 0, 0];
+        let value = parse_bytes(&bytes, InputFormat::MessagePack, true).unwrap();
+        assert_eq!(
+            value,
+            json!([{ "$binary": "aGk=" }, { "$float32": 1.5 }, { "$date": "1970-01-01T00:00:00Z" }])
+        );
+        assert_eq!(typed_primitive_name(value[1].as_object().unwrap()), Some("float32"));
+    }
+
+    #[test]
+    fn cbor_and_bson_streams_become_lists() {
+        let mut cbor = Vec::new();
+        for id in [1, 2] {
+            ciborium::ser::into_writer(&ciborium::Value::Map(vec![("id".into(), id.into())]), &mut cbor).unwrap();
+        }
+        assert_eq!(parse_bytes(&cbor, InputFormat::Cbor, true).unwrap(), json!([{ "id": 1 }, { "id": 2 }]));
+
+        let mut dump = Vec::new();
+        for n in [1i32, 2] {
+            bson::doc! { "n": n, "big": 5i64 }.to_writer(&mut dump).unwrap();
+        }
+        assert_eq!(
+            parse_bytes(&dump, InputFormat::Bson, true).unwrap(),
+            json!([
+                { "n": { "$int32": 1 }, "big": { "$int64": 5 } },
+                { "n": { "$int32": 2 }, "big": { "$int64": 5 } },
+            ])
+        );
+    }
+
+    /// Write `bytes` to a fresh file under the temp directory and return its path
+    fn temp_file(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("json-distiller-input-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn compressed_input_is_detected_from_magic_bytes() {
+        use std::io::Write;
+
+        let content = b"{\"id\": 1}\n{\"id\": 2}\n";
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(content).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let zstd = zstd::encode_all(&content[..], 0).unwrap();
+        let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bzip2.write_all(content).unwrap();
+        let bzip2 = bzip2.finish().unwrap();
+
+        // Misleading extensions: detection goes by content
+        for (name, bytes, compression) in [
+            ("events-gz.ndjson", gzip, Compression::Gzip),
+            ("events-zst.ndjson.gz", zstd, Compression::Zstd),
+            ("events-bz2.ndjson", bzip2, Compression::Bzip2),
+            ("events-plain.ndjson.bz2", content.to_vec(), Compression::None),
+        ] {
+            let path = temp_file(name, &bytes);
+            assert_eq!(open_input(&path).unwrap().1, compression, "{}", name);
+            let value = read_input(&path, InputFormat::Ndjson, false).unwrap();
+            assert_eq!(value, json!([{ "id": 1 }, { "id": 2 }]), "{}", name);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn concatenated_gzip_members_are_read_fully() {
+        use std::io::Write;
+
+        let mut bytes = Vec::new();
+        for line in [&b"{\"id\": 1}\n"[..], &b"{\"id\": 2}\n"[..]] {
+            let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            gzip.write_all(line).unwrap();
+            bytes.extend(gzip.finish().unwrap());
+        }
+        let path = temp_file("parts.ndjson.gz", &bytes);
+        assert_eq!(read_input(&path, InputFormat::Ndjson, false).unwrap(), json!([{ "id": 1 }, { "id": 2 }]));
+        std::fs::remove_file(path).unwrap();
+    }
+}
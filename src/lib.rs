@@ -0,0 +1,15 @@
+// src/lib.rs
+
+pub mod baseline;
+pub mod core;
+pub mod error;
+pub mod har;
+pub mod ignore;
+pub mod input;
+pub mod mcp_server;
+pub mod openapi;
+pub mod redact;
+pub mod select;
+pub mod session;
+pub mod timeline;
+mod structure;
@@ -1,23 +1,38 @@
 // src/main.rs
 
+mod batch;
 mod cli;
-mod core;
-mod error;
-mod mcp_server;
+mod watch;
 
 use anyhow::{bail, Context, Result};
+use batch::InputFile;
 use clap::Parser;
 use cli::CliArgs;
-use error::DistillError;
+use json_distiller::baseline;
+use json_distiller::core::{self, DistillControl, DistillOptions, HashScheme, StructureCache};
+use json_distiller::error::DistillError;
+use json_distiller::har;
+use json_distiller::ignore::IgnoreOptions;
+use json_distiller::input::{self, Compression, InputFormat};
+use json_distiller::mcp_server;
+use json_distiller::openapi::{self, ApiOperation};
+use json_distiller::redact::RedactionOptions;
+use json_distiller::select::{self, Selection};
+use json_distiller::timeline::{self, TimelineOptions};
 use path_absolutize::Absolutize;
+use rayon::prelude::*;
+use serde_json::Value;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use watch::{Fingerprint, StructureCounts, StructureDelta};
 
 fn main() -> Result<()> {
     let args = CliArgs::parse();
 
     if args.mcp_mode {
         // Only initialize tracing and tokio for MCP mode
-        run_mcp_mode()
+        run_mcp_mode(&args)
     } else {
         // CLI mode: pure synchronous execution, no overhead
         run_cli(&args)
@@ -25,7 +40,7 @@ fn main() -> Result<()> {
 }
 
 #[tokio::main]
-async fn run_mcp_mode() -> Result<()> {
+async fn run_mcp_mode(args: &CliArgs) -> Result<()> {
     // Initialize tracing only for MCP server mode where we need it
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -33,46 +48,49 @@ async fn run_mcp_mode() -> Result<()> {
         .init();
 
     tracing::info!("Running in MCP Server mode...");
-    mcp_server::start_mcp().await.context("MCP Server failed")
+    match args.mcp_bind {
+        Some(bind) => mcp_server::start_mcp_http(bind, args.mcp_max_body_bytes)
+            .await
+            .context("MCP Server failed"),
+        None => mcp_server::start_mcp().await.context("MCP Server failed"),
+    }
 }
 
 fn run_cli(args: &CliArgs) -> Result<()> {
     println!("Starting JSON Distiller CLI...");
 
-    let initial_input_path = args.get_input_path()
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("Failed to configure worker threads")?;
+    }
+
+    let input_arguments = args.get_input_paths()
         .map_err(|e| DistillError::InvalidInput(e.to_string()))
         .context("Input file is required when not running in --mcp-server mode")?;
+    let excluded = written_files(args)?;
+    let (inputs, is_batch) = batch::expand_inputs(&input_arguments, &excluded)?;
 
-    let input_path = initial_input_path
-        .absolutize()
-        .context("Failed to make input path absolute")?;
-    let input_path_ref = input_path.as_ref();
-
-     if !input_path_ref.exists() {
-         bail!(DistillError::Io(std::io::Error::new(
-             std::io::ErrorKind::NotFound,
-             format!("Input file not found at '{}'", input_path_ref.display()),
-         )));
-     }
-     if !input_path_ref.is_file() {
-         bail!(DistillError::InvalidInput(format!(
-            "Input path is not a file: '{}'",
-            input_path_ref.display()
-        )));
-     }
+    if args.watch {
+        run_watch(args, &input_arguments, inputs, is_batch)
+    } else if args.snapshot.is_some() || args.check.is_some() {
+        run_baseline(args, &inputs, is_batch)
+    } else if args.per_file {
+        run_per_file(args, &inputs)
+    } else if is_batch {
+        run_collection(args, &inputs)
+    } else {
+        run_single(args, &inputs[0].path)
+    }
+}
 
+fn run_single(args: &CliArgs, input_path_ref: &Path) -> Result<()> {
     let output_path = match &args.output_file {
         Some(path) => path.clone(),
-        None => {
-            let input_filename = input_path_ref
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("output");
-            let default_filename = format!("{}_distilled.json", input_filename);
-            std::env::current_dir()
-                 .context("Failed to get current directory")?
-                 .join(default_filename)
-        }
+        None => std::env::current_dir()
+            .context("Failed to get current directory")?
+            .join(batch::distilled_file_name(input_path_ref)),
     };
 
      let output_path_abs = output_path
@@ -83,31 +101,120 @@ fn run_cli(args: &CliArgs) -> Result<()> {
 
     println!("Input File: {}", input_path_ref.display());
     println!("Output File: {}", output_path_ref.display());
-    println!("Strict Typing: {}", args.strict_typing);
-    println!("Repeat Threshold: {}", args.repeat_threshold);
+    print_settings(args);
 
-    // Read and parse JSON
-    let input_content = fs::read_to_string(input_path_ref)
-        .with_context(|| format!("Failed to read input file: {}", input_path_ref.display()))?;
-
-    let input_json: serde_json::Value = serde_json::from_str(&input_content)
-        .with_context(|| format!("Failed to parse JSON from file: {}", input_path_ref.display()))?;
+    // Read and parse the input (any supported format becomes a JSON value)
+    let input_format = input_format_for(args, input_path_ref);
+    println!("Input Format: {}", input_format);
+    let typed_primitives = args.typed_primitives.unwrap_or(input_format.is_binary());
+    let (reader, compression) = input::open_input(input_path_ref)
+        .with_context(|| format!("Failed to open input file: {}", input_path_ref.display()))?;
+    if compression != Compression::None {
+        println!("Compression: {}", compression);
+    }
+    let input_json = input::parse_reader(reader, input_format, typed_primitives)
+        .with_context(|| format!("Failed to parse {} from file: {}", input_format, input_path_ref.display()))?;
+    let (input_json, selection) = select_input(args, input_json)?;
+    if let Some(selection) = &selection {
+        println!("Selected: {} node(s) matching {}", selection.paths.len(), selection.selector);
+    }
 
     println!("Distilling JSON...");
-    let distilled_json = core::distill_json(input_json, args.strict_typing, args.repeat_threshold, args.position_dependent)
-        .context("Distillation process failed")?;
+    let options = distill_options(args, typed_primitives);
+    if let Some(openapi_path) = &args.openapi {
+        let title = batch::uncompressed_file_name(input_path_ref).to_string();
+        write_openapi(args, openapi_path, &title, &[&input_json], is_har(args, input_path_ref), &options)?;
+    }
+    let mut distilled_json = distill_document(args, input_path_ref, input_json, &options)?;
+    if let Some(selection) = &selection {
+        selection.annotate(&mut distilled_json);
+    }
     println!("Distillation complete.");
 
-    if let Some(parent_dir) = output_path_ref.parent() {
-        fs::create_dir_all(parent_dir)
-            .with_context(|| format!("Failed to create output directory: {}", parent_dir.display()))?;
+    warn_on_collisions(&distilled_json);
+    write_output(output_path_ref, &distilled_json)?;
+
+    println!(
+        "Successfully processed and saved distilled JSON to: {}",
+        output_path_ref.display()
+    );
+
+    Ok(())
+}
+
+/// Distill every input as one item of a virtual list and report how many files each
+/// structure appears in
+fn run_collection(args: &CliArgs, inputs: &[InputFile]) -> Result<()> {
+    let output_path = match &args.output_file {
+        Some(path) => path.clone(),
+        None => std::env::current_dir()
+            .context("Failed to get current directory")?
+            .join("batch_distilled.json"),
+    };
+    let output_path_abs = output_path
+        .absolutize()
+        .context("Failed to make output path absolute")?;
+    let output_path_ref = output_path_abs.as_ref();
+
+    println!("Input Files: {}", inputs.len());
+    println!("Output File: {}", output_path_ref.display());
+    print_settings(args);
+
+    // One typed-primitives setting for the whole collection, so structures stay comparable
+    let typed_primitives = args
+        .typed_primitives
+        .unwrap_or_else(|| inputs.iter().any(|input| input_format_for(args, &input.path).is_binary()));
+
+    println!("Reading {} files...", inputs.len());
+    let documents = inputs
+        .par_iter()
+        .map(|input| {
+            let value = read_document(args, &input.path, typed_primitives)?;
+            Ok((input.display_name.display().to_string(), value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    println!("Distilling JSON...");
+    let options = distill_options(args, typed_primitives);
+    let all_har = inputs.iter().all(|input| is_har(args, &input.path));
+    if let Some(openapi_path) = &args.openapi {
+        let values: Vec<&Value> = documents.iter().map(|(_, value)| value).collect();
+        write_openapi(args, openapi_path, "batch", &values, all_har, &options)?;
+    }
+    let distilled_json = if let Some(timeline_options) = timeline_options(args)? {
+        // Events of all files form one stream; a file holding a single document is one event
+        let events = documents
+            .into_iter()
+            .flat_map(|(_, value)| match value {
+                Value::Array(events) => events,
+                event => vec![event],
+            })
+            .collect();
+        timeline::distill_timeline(events, &timeline_options, &options, &DistillControl::default())
+    } else if all_har {
+        // HAR captures merge into one endpoint catalog
+        har::distill_har(documents.iter().map(|(_, har)| har), &options, &DistillControl::default())
+    } else {
+        core::distill_collection(documents, &options, &DistillControl::default())
     }
+    .context("Distillation process failed")?;
+    println!("Distillation complete.");
 
-    let output_content = serde_json::to_string_pretty(&distilled_json)
-        .context("Failed to serialize distilled JSON")?;
+    if let Some(structures) = distilled_json.pointer("/collection/structures").and_then(|s| s.as_array()) {
+        println!("Most widespread structures:");
+        for structure in structures.iter().take(10) {
+            println!(
+                "  hash {} appears in {} files ({} occurrences): {}",
+                structure["hash"].as_str().unwrap_or_default(),
+                structure["files"],
+                structure["count"],
+                structure["signature"].as_str().unwrap_or_default(),
+            );
+        }
+    }
 
-    fs::write(output_path_ref, output_content)
-         .with_context(|| format!("Failed to write output file: {}", output_path_ref.display()))?;
+    warn_on_collisions(&distilled_json);
+    write_output(output_path_ref, &distilled_json)?;
 
     println!(
         "Successfully processed and saved distilled JSON to: {}",
@@ -115,4 +222,386 @@ fn run_cli(args: &CliArgs) -> Result<()> {
     );
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Save a structure baseline (--snapshot) or check the input against one (--check).
+/// Several inputs are treated as one list, like a collection.
+fn run_baseline(args: &CliArgs, inputs: &[InputFile], is_batch: bool) -> Result<()> {
+    println!("Input Files: {}", inputs.len());
+    print_settings(args);
+
+    let typed_primitives = args
+        .typed_primitives
+        .unwrap_or_else(|| inputs.iter().any(|input| input_format_for(args, &input.path).is_binary()));
+    let mut documents = inputs
+        .par_iter()
+        .map(|input| read_document(args, &input.path, typed_primitives))
+        .collect::<Result<Vec<_>>>()?;
+    let input_json = if is_batch { Value::Array(documents) } else { documents.swap_remove(0) };
+    let options = distill_options(args, typed_primitives);
+
+    if let Some(snapshot_path) = &args.snapshot {
+        let baseline = baseline::snapshot(&input_json, &options).context("Snapshot failed")?;
+        let content = serde_json::to_value(&baseline).context("Failed to serialize baseline")?;
+        let snapshot_path = snapshot_path.absolutize().context("Failed to make output path absolute")?;
+        write_output(&snapshot_path, &content)?;
+        println!(
+            "Saved baseline with {} structures and {} field paths to: {}",
+            baseline.structures.len(),
+            baseline.fields.len(),
+            snapshot_path.display()
+        );
+        return Ok(());
+    }
+
+    if let Some(check_path) = &args.check {
+        let content = fs::read_to_string(check_path)
+            .with_context(|| format!("Failed to read baseline file: {}", check_path.display()))?;
+        let saved: baseline::Baseline = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid baseline file: {}", check_path.display()))?;
+        let report = baseline::check(&input_json, &saved, &options).context("Check failed")?;
+        print!("{}", report);
+        if !report.passed() {
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Re-distill the inputs whenever they change, printing the structural delta of each run.
+/// Runs until interrupted; a run that fails (e.g. on a half-written file) is reported and
+/// retried on the next change.
+fn run_watch(args: &CliArgs, input_arguments: &[PathBuf], inputs: Vec<InputFile>, is_batch: bool) -> Result<()> {
+    if inputs.iter().any(|input| is_har(args, &input.path)) {
+        bail!(DistillError::InvalidInput("--watch doesn't support HAR input".to_string()));
+    }
+    let output_path = match &args.output_file {
+        Some(path) => path.clone(),
+        None => std::env::current_dir()
+            .context("Failed to get current directory")?
+            .join(if is_batch {
+                "batch_distilled.json".to_string()
+            } else {
+                batch::distilled_file_name(&inputs[0].path)
+            }),
+    };
+    let output_path = output_path
+        .absolutize()
+        .context("Failed to make output path absolute")?
+        .into_owned();
+    // The output may sit in a watched directory: left in, it would be distilled as an input
+    // and every write would look like a change, re-running forever
+    let mut excluded = written_files(args)?;
+    excluded.push(output_path.clone());
+    let (mut inputs, mut is_batch) = batch::expand_inputs(input_arguments, &excluded)?;
+
+    println!("Input Files: {}", inputs.len());
+    println!("Output File: {}", output_path.display());
+    print_settings(args);
+    println!("Watching for changes every {} ms (Ctrl-C to stop)...", args.watch_interval);
+
+    let cache = StructureCache::new(HashScheme { algorithm: args.hash_algorithm, length: args.hash_length });
+    let mut previous: Option<StructureCounts> = None;
+    let mut fingerprint = Fingerprint::of(&inputs);
+    let mut run = 1;
+    loop {
+        match distill_watched(args, &inputs, is_batch, &output_path, &cache) {
+            Ok(counts) => {
+                match &previous {
+                    None => println!("Run {}: {} structures", run, counts.len()),
+                    Some(previous) => print!("Run {}: {}", run, StructureDelta::between(previous, &counts)),
+                }
+                previous = Some(counts);
+            }
+            Err(e) => eprintln!("Run {} failed: {:#}", run, e),
+        }
+        run += 1;
+
+        // Poll until an input changes (or files appear in / disappear from a watched directory)
+        loop {
+            std::thread::sleep(Duration::from_millis(args.watch_interval));
+            match batch::expand_inputs(input_arguments, &excluded) {
+                Ok((current, current_batch)) => {
+                    let current_fingerprint = Fingerprint::of(&current);
+                    if current_fingerprint != fingerprint {
+                        fingerprint = current_fingerprint;
+                        inputs = current;
+                        is_batch = current_batch;
+                        break;
+                    }
+                }
+                // Editors may briefly remove a file while saving; wait for it to come back
+                Err(_) => continue,
+            }
+        }
+    }
+}
+
+/// One --watch run: distill the inputs with the shared cache, write the output and
+/// return the structure counts to compare with the next run
+fn distill_watched(
+    args: &CliArgs,
+    inputs: &[InputFile],
+    is_batch: bool,
+    output_path: &Path,
+    cache: &StructureCache,
+) -> Result<StructureCounts> {
+    let typed_primitives = args
+        .typed_primitives
+        .unwrap_or_else(|| inputs.iter().any(|input| input_format_for(args, &input.path).is_binary()));
+    let options = distill_options(args, typed_primitives);
+    let mut documents = inputs
+        .par_iter()
+        .map(|input| read_document(args, &input.path, typed_primitives))
+        .collect::<Result<Vec<_>>>()?;
+
+    let control = DistillControl::default();
+    let (catalog, distilled_json) = if is_batch {
+        // Catalog the documents as the items of one list, like the collection sees them
+        let all = Value::Array(documents);
+        let catalog = core::structure_catalog_with_cache(&all, &options, &control, cache)?;
+        let Value::Array(documents) = all else { unreachable!() };
+        let names = inputs.iter().map(|input| input.display_name.display().to_string());
+        let distilled = core::distill_collection_with_cache(names.zip(documents).collect(), &options, &control, cache)?;
+        (catalog, distilled)
+    } else {
+        let document = documents.swap_remove(0);
+        let catalog = core::structure_catalog_with_cache(&document, &options, &control, cache)?;
+        (catalog, core::distill_json_with_cache(document, &options, &control, cache)?)
+    };
+    warn_on_collisions(&distilled_json);
+    write_output(output_path, &distilled_json)?;
+    Ok(watch::structure_counts(&catalog))
+}
+
+/// Distill each input on its own, in parallel, into `<output dir>/<name>_distilled.json`
+/// (keeping subdirectories of walked directories). A failed file doesn't stop the others.
+fn run_per_file(args: &CliArgs, inputs: &[InputFile]) -> Result<()> {
+    let output_dir = match &args.output_file {
+        Some(path) => path.clone(),
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+    let output_dir = output_dir
+        .absolutize()
+        .context("Failed to make output path absolute")?
+        .into_owned();
+
+    println!("Input Files: {}", inputs.len());
+    println!("Output Directory: {}", output_dir.display());
+    print_settings(args);
+
+    let output_paths: Vec<PathBuf> = inputs
+        .iter()
+        .map(|input| output_dir.join(&input.output_subdir).join(batch::distilled_file_name(&input.path)))
+        .collect();
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = output_paths.iter().find(|path| !seen.insert(*path)) {
+        bail!(DistillError::InvalidInput(format!(
+            "Several inputs would be written to '{}'; rename them or distill them separately",
+            duplicate.display()
+        )));
+    }
+
+    let results: Vec<(&InputFile, Result<PathBuf>)> = inputs
+        .par_iter()
+        .zip(output_paths)
+        .map(|(input, output_path)| {
+            let result = distill_file(args, &input.path, &output_path).map(|_| output_path);
+            (input, result)
+        })
+        .collect();
+
+    let mut failed = 0;
+    for (input, result) in &results {
+        match result {
+            Ok(output_path) => println!("{} -> {}", input.display_name.display(), output_path.display()),
+            Err(e) => {
+                failed += 1;
+                eprintln!("Failed: {}: {:#}", input.display_name.display(), e);
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{} of {} files failed", failed, results.len());
+    }
+    println!("Successfully processed {} files.", results.len());
+    Ok(())
+}
+
+fn distill_file(args: &CliArgs, input_path: &Path, output_path: &Path) -> Result<()> {
+    let typed_primitives = args
+        .typed_primitives
+        .unwrap_or_else(|| input_format_for(args, input_path).is_binary());
+    let (input_json, selection) = read_selected(args, input_path, typed_primitives)?;
+    let options = distill_options(args, typed_primitives);
+    let mut distilled_json = distill_document(args, input_path, input_json, &options)?;
+    if let Some(selection) = &selection {
+        selection.annotate(&mut distilled_json);
+    }
+    warn_on_collisions(&distilled_json);
+    write_output(output_path, &distilled_json)
+}
+
+/// Distill one document, as a HAR capture, an event timeline or as plain data
+fn distill_document(args: &CliArgs, path: &Path, input_json: Value, options: &DistillOptions) -> Result<Value> {
+    if let Some(timeline_options) = timeline_options(args)? {
+        let Value::Array(events) = input_json else {
+            bail!(DistillError::InvalidInput(
+                "--timeline needs a list of events (e.g. NDJSON or a JSON array)".to_string()
+            ));
+        };
+        timeline::distill_timeline(events, &timeline_options, options, &DistillControl::default())
+    } else if is_har(args, path) {
+        har::distill_har([&input_json], options, &DistillControl::default())
+    } else {
+        core::distill_json_with_options(input_json, options, &DistillControl::default())
+    }
+    .context("Distillation process failed")
+}
+
+fn timeline_options(args: &CliArgs) -> Result<Option<TimelineOptions>> {
+    let Some(timestamp_path) = &args.timeline else {
+        return Ok(None);
+    };
+    Ok(Some(TimelineOptions {
+        timestamp_path: timestamp_path.clone(),
+        bucket_seconds: TimelineOptions::parse_bucket(&args.timeline_bucket)?,
+    }))
+}
+
+/// Build an OpenAPI document from HAR captures or from response samples of
+/// --openapi-path, and write it to `output_path`
+fn write_openapi(
+    args: &CliArgs,
+    output_path: &Path,
+    title: &str,
+    documents: &[&Value],
+    har_input: bool,
+    options: &DistillOptions,
+) -> Result<()> {
+    let spec = if har_input {
+        let endpoints = har::extract_endpoints(documents.iter().copied())?;
+        openapi::build_openapi(&ApiOperation::from_har(&endpoints), title, options)
+    } else {
+        let mut operation = ApiOperation::parse(args.openapi_path.as_deref().unwrap_or("GET /"))?;
+        operation.responses = documents.iter().map(|document| (Some(200), *document)).collect();
+        openapi::build_openapi(&[operation], title, options)
+    }
+    .context("OpenAPI export failed")?;
+
+    let output_path = output_path.absolutize().context("Failed to make output path absolute")?;
+    write_output(&output_path, &spec)?;
+    println!("Wrote OpenAPI document to: {}", output_path.display());
+    Ok(())
+}
+
+/// Explicit --har, else by extension. A --select picks a part of the capture, which is
+/// no longer a HAR document, so selecting turns the extension default off.
+fn is_har(args: &CliArgs, path: &Path) -> bool {
+    args.har.unwrap_or_else(|| {
+        if args.select.is_some() {
+            return false;
+        }
+        let file_name = batch::uncompressed_file_name(path);
+        Path::new(file_name).extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("har"))
+    })
+}
+
+fn print_settings(args: &CliArgs) {
+    println!("Strict Typing: {}", args.strict_typing);
+    println!("Repeat Threshold: {}", args.repeat_threshold);
+    println!("Hash Scheme: {}-{}", args.hash_algorithm, args.hash_length);
+    if let Some(selector) = &args.select {
+        println!("Select: {}", selector);
+    }
+    if let Some(timestamp_path) = &args.timeline {
+        println!("Timeline: {} per {}", timestamp_path, args.timeline_bucket);
+    }
+}
+
+fn input_format_for(args: &CliArgs, path: &Path) -> InputFormat {
+    args.input_format.unwrap_or_else(|| InputFormat::from_path(path))
+}
+
+/// Read and parse one input file, decompressing it if needed, and apply --select
+fn read_document(args: &CliArgs, path: &Path, typed_primitives: bool) -> Result<Value> {
+    read_selected(args, path, typed_primitives).map(|(value, _)| value)
+}
+
+/// Like read_document, also returning what --select matched
+fn read_selected(args: &CliArgs, path: &Path, typed_primitives: bool) -> Result<(Value, Option<Selection>)> {
+    let input_format = input_format_for(args, path);
+    let value = input::read_input(path, input_format, typed_primitives)
+        .with_context(|| format!("Failed to parse {} from file: {}", input_format, path.display()))?;
+    select_input(args, value).with_context(|| format!("Failed to select from file: {}", path.display()))
+}
+
+/// The part of a document chosen by --select (the whole document without it)
+fn select_input(args: &CliArgs, value: Value) -> Result<(Value, Option<Selection>)> {
+    let Some(selector) = &args.select else {
+        return Ok((value, None));
+    };
+    let (selected, selection) = select::select(&value, selector)?;
+    Ok((selected, Some(selection)))
+}
+
+fn distill_options(args: &CliArgs, typed_primitives: bool) -> DistillOptions {
+    DistillOptions {
+        strict_typing: args.strict_typing,
+        repeat_threshold: args.repeat_threshold,
+        position_dependent: args.position_dependent,
+        parallel: args.parallel,
+        hash_scheme: HashScheme { algorithm: args.hash_algorithm, length: args.hash_length },
+        max_primitive_samples: args.max_primitive_samples,
+        max_string_len: args.max_string_len,
+        string_length_stats: args.string_length_stats,
+        max_depth: args.max_depth,
+        primitive_order: args.primitive_order,
+        detect_tuples: args.detect_tuples,
+        typed_primitives,
+        redaction: args.redact.then(|| {
+            let defaults = RedactionOptions::default();
+            RedactionOptions {
+                key_patterns: args.redact_keys.clone().unwrap_or(defaults.key_patterns),
+                detectors: args.redact_detectors.clone().unwrap_or(defaults.detectors),
+                strategy: args.redact_strategy,
+            }
+        }),
+        ignore: (!args.ignore_key.is_empty() || !args.ignore_path.is_empty()).then(|| {
+            IgnoreOptions::new(args.ignore_key.clone(), args.ignore_path.clone(), args.ignore_in_examples)
+        }),
+    }
+}
+
+/// Absolute paths of the files named on the command line that a run writes or compares
+/// against, so directory walks and globs don't take them as inputs
+fn written_files(args: &CliArgs) -> Result<Vec<PathBuf>> {
+    [&args.output_file, &args.snapshot, &args.check, &args.openapi]
+        .into_iter()
+        .flatten()
+        .map(|path| Ok(path.absolutize().context("Failed to make output path absolute")?.into_owned()))
+        .collect()
+}
+
+fn warn_on_collisions(distilled_json: &Value) {
+    if let Some(collisions) = distilled_json.get("hash_collisions").and_then(|c| c.as_array()) {
+        eprintln!(
+            "Warning: {} structure hash(es) are shared by distinct structures (see \"hash_collisions\"). \
+             Increase --hash-length to separate them.",
+            collisions.len()
+        );
+    }
+}
+
+fn write_output(output_path: &Path, distilled_json: &Value) -> Result<()> {
+    if let Some(parent_dir) = output_path.parent() {
+        fs::create_dir_all(parent_dir)
+            .with_context(|| format!("Failed to create output directory: {}", parent_dir.display()))?;
+    }
+
+    let output_content = serde_json::to_string_pretty(distilled_json)
+        .context("Failed to serialize distilled JSON")?;
+
+    fs::write(output_path, output_content)
+         .with_context(|| format!("Failed to write output file: {}", output_path.display()))
+}
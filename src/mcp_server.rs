@@ -2,21 +2,44 @@
 
 use rmcp::{
     ErrorData as McpError, RoleServer, ServerHandler, ServiceExt,
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    handler::server::{
+        router::{prompt::PromptRouter, tool::ToolRouter},
+        wrapper::Parameters,
+    },
     model::*,
+    prompt, prompt_handler, prompt_router,
     schemars,
     service::RequestContext,
     tool, tool_handler, tool_router,
 };
+use rmcp::transport::streamable_http_server::{
+    StreamableHttpServerConfig, StreamableHttpService, session::local::LocalSessionManager,
+};
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tower_http::limit::RequestBodyLimitLayer;
 
-use crate::core::distill_json;
+use crate::core::{
+    distill_json_with_options, find_structure_items, structure_catalog, DistillControl, DistillOptions,
+    HashAlgorithm, HashScheme, PrimitiveOrder,
+};
 use crate::error::DistillError;
+use crate::ignore::IgnoreOptions;
+use crate::input::{self, InputFormat};
+use crate::redact::{self, RedactStrategy, RedactionOptions};
+use crate::select::{self, Selection};
+use crate::session::{DocumentStore, StoredDocument};
 
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct DistillRequest {
-    /// The JSON data as a string
+    /// The JSON data as a string (or YAML/TOML/JSON5/JSONC/NDJSON text, see input_format)
     pub json_string: String,
+    /// Format of json_string: "json" (default), "ndjson", "yaml", "toml", "json5" or "jsonc"
+    #[serde(default = "default_input_format")]
+    pub input_format: String,
     /// Use strict type checking (default: true)
     #[serde(default = "default_strict_typing")]
     pub strict_typing: bool,
@@ -27,6 +50,116 @@ pub struct DistillRequest {
     /// When false, shows examples only at shallowest depth (more concise)
     #[serde(default = "default_position_dependent")]
     pub position_dependent: bool,
+    /// Structure hash algorithm: "md5" (default, Python-compatible) or "blake3"
+    #[serde(default = "default_hash_algorithm")]
+    pub hash_algorithm: String,
+    /// Hex characters kept from the hash digest (default: 8). Use 16+ on large,
+    /// heterogeneous documents to avoid collisions between distinct structures
+    #[serde(default = "default_hash_length")]
+    pub hash_length: usize,
+    /// Keep at most this many unique values per list of primitives, followed by a summary
+    /// object with counts, type mix and ranges (default: keep all)
+    #[serde(default)]
+    pub max_primitive_samples: Option<usize>,
+    /// Cut example strings longer than this many characters, ending them with
+    /// "…(+123 chars)" (default: keep whole strings)
+    #[serde(default)]
+    pub max_string_len: Option<usize>,
+    /// With max_string_len, add a "string_lengths" section with the length distribution
+    /// of every field that had strings cut (default: false)
+    #[serde(default)]
+    pub string_length_stats: bool,
+    /// Replace objects and arrays nested deeper than this many levels with stubs holding
+    /// their structure hash, size and one-level signature; expand them with expand_structure
+    /// (default: no limit)
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// How lists of primitives are shown: "sorted" (default, unique values sorted) or
+    /// "preserve" (original order, positional tuples such as [lat, lon] kept intact)
+    #[serde(default = "default_primitive_order")]
+    pub primitive_order: String,
+    /// Give arrays of a list a positional tuple structure (e.g. [float, float]) when they
+    /// share a fixed length and per-position structures (default: false)
+    #[serde(default)]
+    pub detect_tuples: bool,
+    /// Treat single-key objects such as {"$oid": ...}, {"$date": ...} or {"$numberLong": ...}
+    /// (MongoDB Extended JSON) as typed primitives instead of dicts (default: false)
+    #[serde(default)]
+    pub typed_primitives: bool,
+    /// Redact sensitive example values: everything under keys such as password, token or
+    /// email, and emails, card numbers, JWTs and IP addresses anywhere (default: false).
+    /// Raw values returned by drill-down tools for this document are redacted too
+    #[serde(default)]
+    pub redact: bool,
+    /// Replacement for redacted values: "placeholder" (default, e.g. "<email>"), "hash"
+    /// (placeholder plus a short hash, equal values stay equal) or "fake" (same-shape fakes)
+    #[serde(default = "default_redact_strategy")]
+    pub redact_strategy: String,
+    /// Distill only the nodes matched by a JSON Pointer ("/data/0/attributes") or JSONPath
+    /// ("$.data[*].attributes", "$..author", "$.items[0:10]"); several matches are distilled
+    /// as one list. The whole document is kept for drill-down (default: whole document)
+    #[serde(default)]
+    pub select: Option<String>,
+    /// Key names left out of structure detection at any depth, e.g. ["_debug", "extensions"]
+    /// (`*` is a wildcard), so fields varying for uninteresting reasons don't split
+    /// structures (default: none)
+    #[serde(default)]
+    pub ignore_keys: Vec<String>,
+    /// Dotted key paths left out of structure detection, e.g. ["metadata.trace"]. A path may
+    /// start at any object but doesn't cross lists (default: none)
+    #[serde(default)]
+    pub ignore_paths: Vec<String>,
+    /// Also remove ignored members from the examples (default: false, shown but not hashed)
+    #[serde(default)]
+    pub ignore_in_examples: bool,
+}
+
+impl DistillRequest {
+    fn input_format(&self) -> Result<InputFormat, McpError> {
+        self.input_format.parse().map_err(|e: DistillError| McpError {
+            code: ErrorCode(-32602), // Invalid params
+            message: e.to_string().into(),
+            data: None,
+        })
+    }
+
+    fn options(&self) -> Result<DistillOptions, McpError> {
+        let invalid_params = |e: DistillError| McpError {
+            code: ErrorCode(-32602), // Invalid params
+            message: e.to_string().into(),
+            data: None,
+        };
+        let algorithm: HashAlgorithm = self.hash_algorithm.parse().map_err(invalid_params)?;
+        let primitive_order: PrimitiveOrder = self.primitive_order.parse().map_err(invalid_params)?;
+        let redact_strategy: RedactStrategy = self.redact_strategy.parse().map_err(invalid_params)?;
+        let options = DistillOptions {
+            strict_typing: self.strict_typing,
+            repeat_threshold: self.repeat_threshold,
+            position_dependent: self.position_dependent,
+            hash_scheme: HashScheme { algorithm, length: self.hash_length },
+            max_primitive_samples: self.max_primitive_samples,
+            max_string_len: self.max_string_len,
+            string_length_stats: self.string_length_stats,
+            max_depth: self.max_depth,
+            primitive_order,
+            detect_tuples: self.detect_tuples,
+            typed_primitives: self.typed_primitives,
+            redaction: self.redact.then(|| RedactionOptions {
+                strategy: redact_strategy,
+                ..RedactionOptions::default()
+            }),
+            ignore: (!self.ignore_keys.is_empty() || !self.ignore_paths.is_empty()).then(|| {
+                IgnoreOptions::new(self.ignore_keys.clone(), self.ignore_paths.clone(), self.ignore_in_examples)
+            }),
+            ..DistillOptions::default()
+        };
+        options.hash_scheme.validate().map_err(invalid_params)?;
+        Ok(options)
+    }
+}
+
+fn default_input_format() -> String {
+    InputFormat::default().to_string()
 }
 
 fn default_strict_typing() -> bool {
@@ -41,9 +174,127 @@ fn default_position_dependent() -> bool {
     false  // Match Python's default (POSITION_DEPENDENT = False)
 }
 
+fn default_hash_algorithm() -> String {
+    HashAlgorithm::default().to_string()
+}
+
+fn default_primitive_order() -> String {
+    PrimitiveOrder::default().to_string()
+}
+
+fn default_redact_strategy() -> String {
+    RedactStrategy::default().to_string()
+}
+
+fn default_hash_length() -> usize {
+    HashScheme::default().length
+}
+
+/// Options used when a prompt distills an inline json_string
+fn default_options() -> DistillOptions {
+    DistillOptions {
+        strict_typing: default_strict_typing(),
+        repeat_threshold: default_repeat_threshold(),
+        position_dependent: default_position_dependent(),
+        ..DistillOptions::default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ExpandStructureRequest {
+    /// Document id returned by distill_json_content
+    pub doc_id: String,
+    /// Structure hash to expand (as shown in _structure_hash or summarized_pattern)
+    pub hash: String,
+    /// Number of items to return (default: 5)
+    #[serde(default = "default_expand_count")]
+    pub n: usize,
+    /// Number of matching items to skip, for paging through examples (default: 0)
+    #[serde(default)]
+    pub offset: usize,
+    /// Distill each returned item instead of returning it raw (default: false)
+    #[serde(default)]
+    pub distill: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SelectNodesRequest {
+    /// Document id returned by distill_json_content
+    pub doc_id: String,
+    /// JSON Pointer ("/data/0") or JSONPath ("$.data[*].attributes", "$..author", "$.items[0:10]")
+    pub select: String,
+    /// Distill the matched nodes (several matches as one list) instead of returning them raw
+    /// (default: true)
+    #[serde(default = "default_select_distill")]
+    pub distill: bool,
+}
+
+fn default_select_distill() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetPathRequest {
+    /// Document id returned by distill_json_content
+    pub doc_id: String,
+    /// RFC 6901 JSON Pointer into the original document (e.g. "/data/1200")
+    pub json_pointer: String,
+    /// Distill the selected value instead of returning it raw (default: false)
+    #[serde(default)]
+    pub distill: bool,
+}
+
+fn default_expand_count() -> usize {
+    5
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ExplainPromptArgs {
+    /// Document id returned by distill_json_content (or pass json_string instead)
+    pub doc_id: Option<String>,
+    /// The JSON data as a string, distilled with default options
+    pub json_string: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GenerateTypesPromptArgs {
+    /// Document id returned by distill_json_content (or pass json_string instead)
+    pub doc_id: Option<String>,
+    /// The JSON data as a string, distilled with default options
+    pub json_string: Option<String>,
+    /// Target language for the generated types (default: TypeScript)
+    pub language: Option<String>,
+}
+
+/// URI scheme for resources exposing cached documents
+const RESOURCE_SCHEME: &str = "distill://";
+
+/// How often progress notifications are sent while a distillation is running
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Path the streamable HTTP endpoint is mounted at
+const HTTP_ENDPOINT_PATH: &str = "/mcp";
+
+/// How long open HTTP connections (e.g. SSE streams) may take to close after a shutdown signal
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Resource views available for every cached document: (name, description)
+const RESOURCE_VIEWS: [(&str, &str); 2] = [
+    ("distilled", "Distilled output of the document"),
+    ("structures", "Catalog of structure hashes with signatures, counts and first occurrence paths"),
+];
+
 #[derive(Clone)]
 pub struct JsonDistillerServer {
     tool_router: ToolRouter<Self>,
+    prompt_router: PromptRouter<Self>,
+    documents: DocumentStore,
+}
+
+impl Default for JsonDistillerServer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[tool_router]
@@ -51,43 +302,188 @@ impl JsonDistillerServer {
     pub fn new() -> Self {
         Self {
             tool_router: Self::tool_router(),
+            prompt_router: Self::prompt_router(),
+            documents: DocumentStore::new(),
         }
     }
 
-    #[tool(description = "Reverse engineer arbitrary JSON structures for LLM analysis without context overflow. Dramatically increases information density by identifying unique structural patterns and summarizing repetition. Shows one representative example per structure type plus summaries - perfect when you care about structure and patterns, not individual content. Achieves 99%+ compression on repetitive data while preserving full structural information.")]
-    async fn distill_json_content(
+    /// Parse and distill a document string, keeping the document for drill-down tools,
+    /// resources and prompts (see `run_blocking`)
+    async fn distill_and_store(
         &self,
-        Parameters(params): Parameters<DistillRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        tracing::debug!(
-            "Distilling JSON with strict_typing={}, repeat_threshold={}",
-            params.strict_typing,
-            params.repeat_threshold
-        );
+        json_string: String,
+        input_format: InputFormat,
+        options: DistillOptions,
+        select: Option<String>,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<(String, Arc<StoredDocument>), McpError> {
+        let documents = self.documents.clone();
+        Self::run_blocking(context, move |control| {
+            Self::parse_and_distill(&documents, &json_string, input_format, options, select.as_deref(), control)
+        })
+        .await
+    }
+
+    /// Run distillation work on a blocking thread, off the async executor, reporting progress
+    /// to the client (when it sent a progress token) and stopping early if the client cancels
+    /// the request.
+    async fn run_blocking<T: Send + 'static>(
+        context: &RequestContext<RoleServer>,
+        work: impl FnOnce(&DistillControl) -> Result<T, McpError> + Send + 'static,
+    ) -> Result<T, McpError> {
+        let control = DistillControl::default();
+        let worker_control = control.clone();
+
+        let mut task = tokio::task::spawn_blocking(move || work(&worker_control));
+
+        let progress_token = context.meta.get_progress_token();
+        let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+        let mut last_reported = 0;
+
+        let result = loop {
+            tokio::select! {
+                result = &mut task => break result,
+                _ = context.ct.cancelled(), if !control.is_cancelled() => {
+                    tracing::info!("Client cancelled distillation request");
+                    control.cancel();
+                }
+                _ = ticker.tick(), if progress_token.is_some() => {
+                    let items_hashed = control.items_hashed();
+                    if let Some(progress_token) = progress_token.clone().filter(|_| items_hashed > last_reported) {
+                        last_reported = items_hashed;
+                        let notification = ProgressNotificationParam {
+                            progress_token,
+                            progress: items_hashed as f64,
+                            total: None,
+                            message: Some(format!("{} items hashed", items_hashed)),
+                        };
+                        if let Err(e) = context.peer.notify_progress(notification).await {
+                            tracing::debug!("Failed to send progress notification: {:?}", e);
+                        }
+                    }
+                }
+            }
+        };
 
-        // Parse the input JSON string
-        let input_value: serde_json::Value = serde_json::from_str(&params.json_string)
+        result.map_err(|e| McpError {
+            code: ErrorCode(-32603), // Internal error
+            message: format!("Distillation task failed: {}", e).into(),
+            data: None,
+        })?
+    }
+
+    fn parse_and_distill(
+        documents: &DocumentStore,
+        json_string: &str,
+        input_format: InputFormat,
+        options: DistillOptions,
+        select: Option<&str>,
+        control: &DistillControl,
+    ) -> Result<(String, Arc<StoredDocument>), McpError> {
+        // Parse the input string (any supported format becomes a JSON value)
+        let input_value = input::parse_str(json_string, input_format)
             .map_err(|e| McpError {
                 code: ErrorCode(-32602), // Invalid params
-                message: format!("Failed to parse JSON: {}", e).into(),
+                message: format!("Failed to parse {}: {}", input_format, e).into(),
                 data: None,
             })?;
 
-        // Perform distillation
-        let distilled_value = distill_json(
-            input_value,
-            params.strict_typing,
-            params.repeat_threshold,
-            params.position_dependent,
-        )
+        // Perform distillation (the raw value is kept for expand_structure/get_path, whole
+        // even when only a selection is distilled, so paths and hashes stay valid)
+        let selected = select.map(|selector| Self::select(&input_value, selector)).transpose()?;
+        let to_distill = match &selected {
+            Some((value, _)) => value.clone(),
+            None => input_value.clone(),
+        };
+        let mut distilled_value = distill_json_with_options(to_distill, &options, control)
         .map_err(|e: DistillError| McpError {
             code: ErrorCode(-32603), // Internal error
             message: format!("Distillation failed: {}", e).into(),
             data: None,
         })?;
+        if let Some((_, selection)) = &selected {
+            selection.annotate(&mut distilled_value);
+        }
 
-        // Convert result to pretty JSON string
-        let result_string = serde_json::to_string_pretty(&distilled_value).map_err(|e| {
+        // Everything that shapes the stored result is part of the document id
+        let settings = format!("{} {:?} select={:?}", input_format, options, select);
+        Ok(documents.insert(
+            json_string,
+            &settings,
+            StoredDocument {
+                data: input_value,
+                distilled: distilled_value,
+                options,
+            },
+        ))
+    }
+
+    fn get_document(&self, doc_id: &str) -> Result<Arc<StoredDocument>, McpError> {
+        self.documents.get(doc_id).ok_or_else(|| McpError {
+            code: ErrorCode(-32602), // Invalid params
+            message: format!(
+                "Unknown doc_id '{}'. Documents expire from the session cache; call distill_json_content again.",
+                doc_id
+            ).into(),
+            data: None,
+        })
+    }
+
+    /// Distill a slice of a stored document with the options it was distilled with
+    fn distill_slice(
+        document: &StoredDocument,
+        value: serde_json::Value,
+        control: &DistillControl,
+    ) -> Result<serde_json::Value, McpError> {
+        let mut distilled = distill_json_with_options(value, &document.options, control)
+        .map_err(|e: DistillError| McpError {
+            code: ErrorCode(-32603), // Internal error
+            message: format!("Distillation failed: {}", e).into(),
+            data: None,
+        })?;
+        Ok(distilled
+            .get_mut("distilled_data")
+            .map(serde_json::Value::take)
+            .unwrap_or(serde_json::Value::Null))
+    }
+
+    fn select(data: &serde_json::Value, selector: &str) -> Result<(serde_json::Value, Selection), McpError> {
+        select::select(data, selector).map_err(|e| McpError {
+            code: ErrorCode(-32602), // Invalid params
+            message: e.to_string().into(),
+            data: None,
+        })
+    }
+
+    /// A raw value at `pointer` of a document, redacted if the document was distilled with
+    /// redaction (entirely, if any key on the path is sensitive)
+    fn raw_value(document: &StoredDocument, pointer: &str, mut value: serde_json::Value) -> serde_json::Value {
+        if let Some(redaction) = &document.options.redaction {
+            let sensitive = pointer.split('/').any(|token| redaction.is_sensitive_key(token));
+            redact::redact_value(&mut value, redaction, sensitive);
+        }
+        value
+    }
+
+    fn catalog(document: &StoredDocument, control: &DistillControl) -> Result<serde_json::Value, McpError> {
+        structure_catalog(&document.data, &document.options, control).map_err(|e: DistillError| McpError {
+            code: ErrorCode(-32603), // Internal error
+            message: format!("Structure catalog failed: {}", e).into(),
+            data: None,
+        })
+    }
+
+    /// `catalog` on a blocking thread (see `run_blocking`)
+    async fn catalog_blocking(
+        document: &Arc<StoredDocument>,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<serde_json::Value, McpError> {
+        let document = Arc::clone(document);
+        Self::run_blocking(context, move |control| Self::catalog(&document, control)).await
+    }
+
+    fn json_result(value: &serde_json::Value) -> Result<CallToolResult, McpError> {
+        let result_string = serde_json::to_string_pretty(value).map_err(|e| {
             McpError {
                 code: ErrorCode(-32603), // Internal error
                 message: format!("Failed to serialize result: {}", e).into(),
@@ -99,27 +495,354 @@ impl JsonDistillerServer {
             result_string,
         )]))
     }
+
+    #[tool(description = "Reverse engineer arbitrary JSON structures for LLM analysis without context overflow. Dramatically increases information density by identifying unique structural patterns and summarizing repetition. Shows one representative example per structure type plus summaries - perfect when you care about structure and patterns, not individual content. Achieves 99%+ compression on repetitive data while preserving full structural information.")]
+    async fn distill_json_content(
+        &self,
+        Parameters(params): Parameters<DistillRequest>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        tracing::debug!(
+            "Distilling JSON with strict_typing={}, repeat_threshold={}",
+            params.strict_typing,
+            params.repeat_threshold
+        );
+
+        let options = params.options()?;
+        let input_format = params.input_format()?;
+        let (doc_id, document) = self
+            .distill_and_store(params.json_string, input_format, options, params.select, &context)
+            .await?;
+
+        let mut distilled_value = document.distilled.clone();
+        if let serde_json::Value::Object(map) = &mut distilled_value {
+            map.insert("doc_id".to_string(), serde_json::Value::String(doc_id));
+        }
+
+        Self::json_result(&distilled_value)
+    }
+
+    #[tool(description = "Drill down into a previously distilled document: return up to n raw items (or sub-distilled items) that share a given structure hash. Use the doc_id returned by distill_json_content and a hash from '_structure_hash' or 'summarized_pattern'. Use offset to page through further examples.")]
+    async fn expand_structure(
+        &self,
+        Parameters(params): Parameters<ExpandStructureRequest>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        tracing::debug!(
+            "Expanding structure {} in document {} (n={}, offset={})",
+            params.hash,
+            params.doc_id,
+            params.n,
+            params.offset
+        );
+
+        let document = self.get_document(&params.doc_id)?;
+        let result = Self::run_blocking(&context, move |control| {
+            let matches = find_structure_items(
+                &document.data,
+                &params.hash,
+                &document.options,
+                params.offset,
+                params.n,
+                control,
+            )
+            .map_err(|e: DistillError| McpError {
+                code: ErrorCode(-32603), // Internal error
+                message: format!("Structure lookup failed: {}", e).into(),
+                data: None,
+            })?;
+
+            let mut items = Vec::with_capacity(matches.items.len());
+            for (path, item) in matches.items {
+                let value = if params.distill {
+                    Self::distill_slice(&document, item, control)?
+                } else {
+                    Self::raw_value(&document, &path, item)
+                };
+                items.push(serde_json::json!({ "path": path, "value": value }));
+            }
+
+            Ok(serde_json::json!({
+                "doc_id": params.doc_id,
+                "structure_hash": params.hash,
+                "total_matches": matches.total,
+                "offset": params.offset,
+                "items": items,
+            }))
+        })
+        .await?;
+        Self::json_result(&result)
+    }
+
+    #[tool(description = "Focus on a branch of a previously distilled document without re-sending it: select nodes with a JSON Pointer (e.g. \"/data/0/attributes\") or JSONPath (e.g. \"$.data[*].attributes\", \"$..author\") and distill them (several matches as one list), or return them raw. Use the doc_id returned by distill_json_content.")]
+    async fn select_nodes(
+        &self,
+        Parameters(params): Parameters<SelectNodesRequest>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        tracing::debug!("Selecting {} in document {}", params.select, params.doc_id);
+
+        let document = self.get_document(&params.doc_id)?;
+        let result = Self::run_blocking(&context, move |control| {
+            let (value, selection) = Self::select(&document.data, &params.select)?;
+            if params.distill {
+                let value = Self::distill_slice(&document, value, control)?;
+                return Ok(serde_json::json!({
+                    "doc_id": params.doc_id,
+                    "selection": selection.summary(),
+                    "value": value,
+                }));
+            }
+
+            let values = match value {
+                serde_json::Value::Array(items) if !selection.definite => items,
+                value => vec![value],
+            };
+            let items: Vec<serde_json::Value> = selection
+                .paths
+                .iter()
+                .zip(values)
+                .map(|(path, item)| serde_json::json!({ "path": path, "value": Self::raw_value(&document, path, item) }))
+                .collect();
+            Ok(serde_json::json!({
+                "doc_id": params.doc_id,
+                "selector": selection.selector,
+                "matches": items.len(),
+                "items": items,
+            }))
+        })
+        .await?;
+        Self::json_result(&result)
+    }
+
+    #[tool(description = "Return the value at a JSON Pointer (e.g. \"/data/1200\" or \"/meta/pagination\") in a previously distilled document, either raw or distilled. Use the doc_id returned by distill_json_content.")]
+    async fn get_path(
+        &self,
+        Parameters(params): Parameters<GetPathRequest>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        tracing::debug!("Reading path {} in document {}", params.json_pointer, params.doc_id);
+
+        let document = self.get_document(&params.doc_id)?;
+        let value = document.data.pointer(&params.json_pointer).cloned().ok_or_else(|| McpError {
+            code: ErrorCode(-32602), // Invalid params
+            message: format!("No value at JSON Pointer '{}'", params.json_pointer).into(),
+            data: None,
+        })?;
+
+        let value = if params.distill {
+            Self::run_blocking(&context, move |control| Self::distill_slice(&document, value, control)).await?
+        } else {
+            Self::raw_value(&document, &params.json_pointer, value)
+        };
+
+        Self::json_result(&serde_json::json!({
+            "doc_id": params.doc_id,
+            "path": params.json_pointer,
+            "value": value,
+        }))
+    }
+}
+
+#[prompt_router]
+impl JsonDistillerServer {
+    /// Resolve prompt arguments to a cached document, distilling json_string if given
+    async fn prompt_document(
+        &self,
+        doc_id: Option<String>,
+        json_string: Option<String>,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<Arc<StoredDocument>, McpError> {
+        match (doc_id, json_string) {
+            (Some(doc_id), _) => self.get_document(&doc_id),
+            (None, Some(json_string)) => self
+                .distill_and_store(json_string, InputFormat::Json, default_options(), None, context)
+                .await
+                .map(|(_, document)| document),
+            (None, None) => Err(McpError {
+                code: ErrorCode(-32602), // Invalid params
+                message: "Either doc_id or json_string is required".into(),
+                data: None,
+            }),
+        }
+    }
+
+    fn pretty(value: &serde_json::Value) -> String {
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    }
+
+    #[prompt(
+        name = "explain_api_response",
+        description = "Explain the structure and meaning of a JSON API response, using its distillation and structure catalog."
+    )]
+    async fn explain_api_response(
+        &self,
+        Parameters(args): Parameters<ExplainPromptArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<Vec<PromptMessage>, McpError> {
+        let document = self.prompt_document(args.doc_id, args.json_string, &context).await?;
+        let catalog = Self::catalog_blocking(&document, &context).await?;
+
+        Ok(vec![PromptMessage::new_text(
+            PromptMessageRole::User,
+            format!(
+                "Explain this API response. Describe what each part of the payload represents, how the \
+                 repeated structures relate to each other, and anything notable about optional or \
+                 varying fields.\n\nThe response was distilled: lists show one example per unique \
+                 structure, and 'summarized_pattern' objects stand in for the remaining items.\n\n\
+                 Distilled response:\n{}\n\nStructure catalog:\n{}",
+                Self::pretty(&document.distilled),
+                Self::pretty(&catalog)
+            ),
+        )])
+    }
+
+    #[prompt(
+        name = "generate_types",
+        description = "Generate type definitions (TypeScript by default) for a JSON payload, using its distillation and structure catalog."
+    )]
+    async fn generate_types(
+        &self,
+        Parameters(args): Parameters<GenerateTypesPromptArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<Vec<PromptMessage>, McpError> {
+        let document = self.prompt_document(args.doc_id, args.json_string, &context).await?;
+        let catalog = Self::catalog_blocking(&document, &context).await?;
+        let language = args.language.as_deref().unwrap_or("TypeScript");
+
+        Ok(vec![PromptMessage::new_text(
+            PromptMessageRole::User,
+            format!(
+                "Generate {} type definitions for this JSON payload. Create one named type per distinct \
+                 structure in the catalog, reuse types where the same hash appears in several places, and \
+                 mark fields as optional when sibling structures differ only by missing keys.\n\n\
+                 Distilled payload:\n{}\n\nStructure catalog:\n{}",
+                language,
+                Self::pretty(&document.distilled),
+                Self::pretty(&catalog)
+            ),
+        )])
+    }
 }
 
 #[tool_handler]
+#[prompt_handler]
 impl ServerHandler for JsonDistillerServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
+                .enable_resources()
+                .enable_prompts()
                 .build(),
             server_info: Implementation::from_build_env(),
             instructions: Some(
                 "Analyze and reverse engineer arbitrary JSON structures for LLMs. This tool dramatically increases information \
                 density (99%+ compression) by identifying unique structural patterns in large JSON payloads. Perfect for \
                 understanding API responses, datasets, and complex JSON without overwhelming context windows. Preserves complete \
-                structural information while removing repetitive content."
+                structural information while removing repetitive content. Each distillation returns a doc_id; use \
+                expand_structure to see more items of a structure hash and get_path to read any JSON Pointer in the \
+                original document without re-sending it. Cached documents are also exposed as resources \
+                (distill://<doc_id>/distilled and distill://<doc_id>/structures), and the explain_api_response and \
+                generate_types prompts embed a distillation for you."
                     .to_string(),
             ),
         }
     }
 
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let resources = self
+            .documents
+            .ids()
+            .into_iter()
+            .flat_map(|doc_id| {
+                RESOURCE_VIEWS.iter().map(move |(view, description)| {
+                    let mut resource = RawResource::new(
+                        format!("{}{}/{}", RESOURCE_SCHEME, doc_id, view),
+                        format!("{} {}", doc_id, view),
+                    );
+                    resource.description = Some(description.to_string());
+                    resource.mime_type = Some("application/json".to_string());
+                    resource.no_annotation()
+                })
+            })
+            .collect();
+
+        Ok(ListResourcesResult {
+            resources,
+            next_cursor: None,
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        let resource_templates = RESOURCE_VIEWS
+            .iter()
+            .map(|(view, description)| {
+                RawResourceTemplate {
+                    uri_template: format!("{}{{doc_id}}/{}", RESOURCE_SCHEME, view),
+                    name: format!("Document {}", view),
+                    title: None,
+                    description: Some(description.to_string()),
+                    mime_type: Some("application/json".to_string()),
+                }
+                .no_annotation()
+            })
+            .collect();
+
+        Ok(ListResourceTemplatesResult {
+            resource_templates,
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let not_found = || McpError {
+            code: ErrorCode::RESOURCE_NOT_FOUND,
+            message: format!("Resource not found: {}", request.uri).into(),
+            data: None,
+        };
+
+        let (doc_id, view) = request
+            .uri
+            .strip_prefix(RESOURCE_SCHEME)
+            .and_then(|rest| rest.split_once('/'))
+            .ok_or_else(not_found)?;
+        let document = self.documents.get(doc_id).ok_or_else(not_found)?;
+
+        let value = match view {
+            "distilled" => document.distilled.clone(),
+            "structures" => Self::catalog_blocking(&document, &context).await?,
+            _ => return Err(not_found()),
+        };
+        let text = serde_json::to_string_pretty(&value).map_err(|e| McpError {
+            code: ErrorCode(-32603), // Internal error
+            message: format!("Failed to serialize result: {}", e).into(),
+            data: None,
+        })?;
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: request.uri.clone(),
+                mime_type: Some("application/json".to_string()),
+                text,
+                meta: None,
+            }],
+        })
+    }
+
     async fn initialize(
         &self,
         _request: InitializeRequestParam,
@@ -152,3 +875,82 @@ pub async fn start_mcp() -> anyhow::Result<()> {
     tracing::info!("MCP server shutdown");
     Ok(())
 }
+
+/// Start the MCP server with streamable HTTP transport
+pub async fn start_mcp_http(bind: SocketAddr, max_body_bytes: usize) -> anyhow::Result<()> {
+    tracing::info!("Starting JSON Distiller MCP server...");
+
+    // Each HTTP session gets its own server instance, and therefore its own document cache
+    let service = StreamableHttpService::new(
+        || Ok(JsonDistillerServer::new()),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    );
+    let router = axum::Router::new()
+        .nest_service(HTTP_ENDPOINT_PATH, service)
+        .layer(RequestBodyLimitLayer::new(max_body_bytes));
+
+    let listener = tokio::net::TcpListener::bind(bind).await.inspect_err(|e| {
+        tracing::error!("Failed to bind {}: {:?}", bind, e);
+    })?;
+
+    tracing::info!(
+        "MCP server running on http://{}{} (max request body: {} bytes)",
+        listener.local_addr()?,
+        HTTP_ENDPOINT_PATH,
+        max_body_bytes
+    );
+
+    let ct = CancellationToken::new();
+    tokio::spawn(wait_for_shutdown_signal(ct.clone()));
+
+    let server = axum::serve(listener, router).with_graceful_shutdown(ct.clone().cancelled_owned());
+    let grace_period_elapsed = async {
+        ct.cancelled().await;
+        tokio::time::sleep(SHUTDOWN_GRACE_PERIOD).await;
+    };
+
+    // Long-lived SSE streams never finish on their own, so bound the graceful shutdown
+    tokio::select! {
+        result = server => result?,
+        _ = grace_period_elapsed => {
+            tracing::warn!("Connections still open after {:?}; forcing shutdown", SHUTDOWN_GRACE_PERIOD);
+        }
+    }
+
+    tracing::info!("MCP server shutdown");
+    Ok(())
+}
+
+/// Cancel `ct` on Ctrl+C (and SIGTERM on Unix)
+async fn wait_for_shutdown_signal(ct: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutdown signal received");
+    ct.cancel();
+}
@@ -0,0 +1,431 @@
+// src/openapi.rs - OpenAPI 3.1 export from observed payloads
+
+use crate::core::{self, DistillOptions};
+use crate::error::{DistillError, Result};
+use crate::har::HarEndpoint;
+use crate::structure::{DeepStructureKey, StructureInterner, StructureKey};
+use indexmap::{IndexMap, IndexSet};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde_json::{json, Map, Value};
+
+/// Observed traffic of one API operation
+#[derive(Debug, Clone)]
+pub struct ApiOperation<'a> {
+    /// HTTP method, e.g. `GET`
+    pub method: String,
+    /// Path template with `{name}` parameters, e.g. `/users/{id}`
+    pub path: String,
+    /// Server the operation was observed on, if known (`https://api.example.com`)
+    pub server: Option<String>,
+    /// JSON request bodies
+    pub request_bodies: Vec<&'a Value>,
+    /// JSON response bodies with their status (`None` if unknown)
+    pub responses: Vec<(Option<u16>, &'a Value)>,
+}
+
+impl<'a> ApiOperation<'a> {
+    /// Parse an operation given as `"GET /users/{id}"` (method defaults to GET)
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut parts = spec.split_whitespace();
+        let (method, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(path), None, None) if path.starts_with('/') => ("GET", path),
+            (Some(method), Some(path), None) if path.starts_with('/') => (method, path),
+            _ => {
+                return Err(DistillError::InvalidInput(format!(
+                    "Invalid operation '{}' (expected e.g. \"GET /users/{{id}}\")",
+                    spec
+                )))
+            }
+        };
+        Ok(Self {
+            method: method.to_ascii_uppercase(),
+            path: path.to_string(),
+            server: None,
+            request_bodies: Vec::new(),
+            responses: Vec::new(),
+        })
+    }
+
+    /// One operation per HAR endpoint
+    pub fn from_har(endpoints: &'a [HarEndpoint]) -> Vec<Self> {
+        endpoints
+            .iter()
+            .map(|endpoint| Self {
+                method: endpoint.method.clone(),
+                path: endpoint.path_template.clone(),
+                server: Some(endpoint.origin.clone()).filter(|origin| !origin.is_empty()),
+                request_bodies: endpoint.request_bodies.iter().collect(),
+                responses: endpoint.response_bodies.iter().map(|(status, body)| (*status, body)).collect(),
+            })
+            .collect()
+    }
+}
+
+/// Build an OpenAPI 3.1 document from observed operations.
+/// Every distinct structure that is a request/response body, a list item, or that appears
+/// under more than one parent becomes a schema in `components/schemas` (annotated with its
+/// `x-structure-hash`, the `_structure_hash` of distilled output) and is referenced with
+/// `$ref` wherever it occurs; other objects are inlined. Structure keys follow `options`,
+/// so e.g. `strict_typing = false` produces untyped leaves.
+pub fn build_openapi(operations: &[ApiOperation], title: &str, options: &DistillOptions) -> Result<Value> {
+    options.hash_scheme.validate()?;
+    let interner = StructureInterner::new(options.hash_scheme);
+    let mut builder = SchemaBuilder::default();
+
+    // Structure keys of every body, per operation. Operations seen on several servers
+    // (same method and path) are merged into one.
+    let mut keyed: IndexMap<(String, String), KeyedOperation> = IndexMap::new();
+    let mut servers: IndexSet<&str> = IndexSet::new();
+    for operation in operations {
+        if let Some(server) = &operation.server {
+            servers.insert(server);
+        }
+        let body_name = pascal_case(&operation_id(&operation.method, &operation.path));
+        let entry = keyed
+            .entry((operation.path.clone(), operation.method.to_ascii_lowercase()))
+            .or_default();
+        for body in &operation.request_bodies {
+            let key = core::structure_key(body, options, &interner)?;
+            builder.visit_root(&key, &format!("{}Request", body_name));
+            entry.requests.insert(key);
+        }
+        for (status, body) in &operation.responses {
+            let key = core::structure_key(body, options, &interner)?;
+            // Error bodies get the status in their name (GetUsersIdResponse404)
+            let suffix = status.filter(|s| !(200..300).contains(s)).map(|s| s.to_string()).unwrap_or_default();
+            builder.visit_root(&key, &format!("{}Response{}", body_name, suffix));
+            entry.responses.entry(*status).or_default().insert(key);
+        }
+    }
+    builder.assign_components();
+
+    let mut paths: IndexMap<&str, Map<String, Value>> = IndexMap::new();
+    for ((path, method), KeyedOperation { requests, responses }) in &keyed {
+        let mut op = Map::new();
+        op.insert("operationId".to_string(), Value::String(operation_id(method, path)));
+        let parameters = path_parameters(path);
+        if !parameters.is_empty() {
+            op.insert("parameters".to_string(), Value::Array(parameters));
+        }
+        if !requests.is_empty() {
+            op.insert("requestBody".to_string(), json!({
+                "content": { "application/json": { "schema": builder.union(requests.iter()) } }
+            }));
+        }
+        let mut response_map = Map::new();
+        for (status, keys) in responses {
+            let status = status.map_or_else(|| "default".to_string(), |s| s.to_string());
+            response_map.insert(status, json!({
+                "description": format!("Observed response ({} distinct structure(s))", keys.len()),
+                "content": { "application/json": { "schema": builder.union(keys.iter()) } }
+            }));
+        }
+        if response_map.is_empty() {
+            response_map.insert("default".to_string(), json!({ "description": "No JSON response observed" }));
+        }
+        op.insert("responses".to_string(), Value::Object(response_map));
+        paths.entry(path).or_default().insert(method.clone(), Value::Object(op));
+    }
+
+    let mut document = Map::new();
+    document.insert("openapi".to_string(), Value::String("3.1.0".to_string()));
+    document.insert("info".to_string(), json!({
+        "title": title,
+        "version": "0.0.0",
+        "description": format!(
+            "Generated by json-distiller from observed payloads ({} operations, {} schemas). \
+             Schemas describe what was seen, not everything the API may return. \
+             x-structure-hash matches _structure_hash in distilled output ({}).",
+            keyed.len(),
+            builder.components.len(),
+            options.hash_scheme
+        ),
+    }));
+    if !servers.is_empty() {
+        let servers: Vec<Value> = servers.into_iter().map(|url| json!({ "url": url })).collect();
+        document.insert("servers".to_string(), Value::Array(servers));
+    }
+    let paths: Map<String, Value> = paths
+        .into_iter()
+        .map(|(path, item)| (path.to_string(), Value::Object(item)))
+        .collect();
+    document.insert("paths".to_string(), Value::Object(paths));
+    document.insert("components".to_string(), json!({ "schemas": builder.component_schemas() }));
+    Ok(Value::Object(document))
+}
+
+#[derive(Default)]
+struct KeyedOperation {
+    requests: IndexSet<StructureKey>,
+    responses: IndexMap<Option<u16>, IndexSet<StructureKey>>,
+}
+
+/// Decides which structures become components and renders schemas
+#[derive(Default)]
+struct SchemaBuilder {
+    /// Parents referencing each dict structure (each distinct parent counted once)
+    references: FxHashMap<StructureKey, usize>,
+    /// Dict structures that must be components (bodies and list items)
+    always_component: FxHashSet<StructureKey>,
+    /// First-seen name hint per dict structure, in first-seen order
+    name_hints: IndexMap<StructureKey, String>,
+    /// Structures whose children were already visited
+    visited: FxHashSet<StructureKey>,
+    /// Component name per structure
+    components: IndexMap<StructureKey, String>,
+}
+
+impl SchemaBuilder {
+    fn visit_root(&mut self, key: &StructureKey, name_hint: &str) {
+        if matches!(key.node(), DeepStructureKey::Dict(_)) {
+            self.always_component.insert(key.clone());
+        }
+        self.visit(key, name_hint, false);
+    }
+
+    fn visit(&mut self, key: &StructureKey, name_hint: &str, list_item: bool) {
+        if let DeepStructureKey::Dict(_) = key.node() {
+            *self.references.entry(key.clone()).or_insert(0) += 1;
+            self.name_hints.entry(key.clone()).or_insert_with(|| name_hint.to_string());
+            if list_item {
+                self.always_component.insert(key.clone());
+            }
+        }
+        // Children are counted once per distinct parent, not once per occurrence
+        if !self.visited.insert(key.clone()) {
+            return;
+        }
+        match key.node() {
+            DeepStructureKey::Dict(pairs) => {
+                for (name, child) in pairs {
+                    self.visit(child, &pascal_case(name), false);
+                }
+            }
+            DeepStructureKey::List(items) | DeepStructureKey::Tuple(items) => {
+                let item_hint = singular(name_hint);
+                for item in items {
+                    self.visit(item, &item_hint, true);
+                }
+            }
+            DeepStructureKey::Primitive(_) | DeepStructureKey::EmptyList => {}
+        }
+    }
+
+    fn assign_components(&mut self) {
+        let mut used: FxHashSet<String> = FxHashSet::default();
+        for (key, hint) in &self.name_hints {
+            let shared = self.references.get(key).copied().unwrap_or(0) > 1;
+            if !(shared || self.always_component.contains(key)) {
+                continue;
+            }
+            let base = if hint.is_empty() { "Object".to_string() } else { hint.clone() };
+            // Different structures under the same name are told apart by their hash
+            let name = if used.contains(&base) { format!("{}_{}", base, key.hash()) } else { base };
+            used.insert(name.clone());
+            self.components.insert(key.clone(), name);
+        }
+    }
+
+    fn component_schemas(&self) -> Map<String, Value> {
+        self.components
+            .iter()
+            .map(|(key, name)| {
+                let mut schema = self.inline_schema(key);
+                if let Value::Object(map) = &mut schema {
+                    map.insert("x-structure-hash".to_string(), Value::String(key.hash().to_string()));
+                }
+                (name.clone(), schema)
+            })
+            .collect()
+    }
+
+    /// `$ref` for components, the inline schema otherwise
+    fn schema(&self, key: &StructureKey) -> Value {
+        match self.components.get(key) {
+            Some(name) => json!({ "$ref": format!("#/components/schemas/{}", name) }),
+            None => self.inline_schema(key),
+        }
+    }
+
+    fn inline_schema(&self, key: &StructureKey) -> Value {
+        match key.node() {
+            DeepStructureKey::Primitive(name) => primitive_schema(name),
+            DeepStructureKey::Dict(pairs) => {
+                let properties: Map<String, Value> =
+                    pairs.iter().map(|(name, child)| (name.clone(), self.schema(child))).collect();
+                // A structure is one exact key set, so every key was present
+                let required: Vec<&str> = pairs.iter().map(|(name, _)| name.as_str()).collect();
+                json!({ "type": "object", "properties": properties, "required": required })
+            }
+            DeepStructureKey::List(items) => json!({ "type": "array", "items": self.union(items.iter()) }),
+            DeepStructureKey::Tuple(items) => {
+                let prefix_items: Vec<Value> = items.iter().map(|item| self.schema(item)).collect();
+                json!({
+                    "type": "array",
+                    "prefixItems": prefix_items,
+                    "minItems": items.len(),
+                    "maxItems": items.len(),
+                })
+            }
+            DeepStructureKey::EmptyList => json!({ "type": "array" }),
+        }
+    }
+
+    /// One schema for several alternative structures. `anyOf` rather than `oneOf`,
+    /// since e.g. an integer also matches a number schema.
+    fn union<'k>(&self, keys: impl ExactSizeIterator<Item = &'k StructureKey>) -> Value {
+        let mut schemas: Vec<Value> = keys.map(|key| self.schema(key)).collect();
+        if schemas.len() == 1 {
+            schemas.swap_remove(0)
+        } else {
+            json!({ "anyOf": schemas })
+        }
+    }
+}
+
+fn primitive_schema(type_name: &str) -> Value {
+    match type_name {
+        "NoneType" => json!({ "type": "null" }),
+        "bool" => json!({ "type": "boolean" }),
+        "str" | "Decimal128" | "bigint" | "Regex" | "Code" | "Symbol" => json!({ "type": "string" }),
+        "int" => json!({ "type": "integer" }),
+        "float" => json!({ "type": "number" }),
+        "int8" | "int16" | "int32" | "int64" | "uint8" | "uint16" | "uint32" | "uint64" => {
+            json!({ "type": "integer", "format": type_name })
+        }
+        "float32" => json!({ "type": "number", "format": "float" }),
+        "ObjectId" => json!({ "type": "string", "pattern": "^[0-9a-f]{24}$" }),
+        "datetime" => json!({ "type": "string", "format": "date-time" }),
+        "bytes" => json!({ "type": "string", "contentEncoding": "base64" }),
+        // "value" (strict_typing off) and the remaining typed primitives
+        _ => json!({}),
+    }
+}
+
+/// Path parameters of a template; `{id}`-style names (as produced for numeric HAR
+/// segments) are integers, everything else a string
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            let is_id = name.strip_prefix("id").is_some_and(|n| n.bytes().all(|b| b.is_ascii_digit()));
+            let schema = if is_id { json!({ "type": "integer" }) } else { json!({ "type": "string" }) };
+            json!({ "name": name, "in": "path", "required": true, "schema": schema })
+        })
+        .collect()
+}
+
+/// `GET /users/{id}/posts` -> `getUsersIdPosts`
+fn operation_id(method: &str, path: &str) -> String {
+    let mut id = method.to_ascii_lowercase();
+    id.push_str(&pascal_case(path));
+    id
+}
+
+/// `user_profile` / `user-profile` / `userProfile` -> `UserProfile`
+fn pascal_case(s: &str) -> String {
+    s.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Naive English singular for list item names (`Users` -> `User`, `Entries` -> `Entry`)
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if name.ends_with("ss") {
+        name.to_string()
+    } else if let Some(stem) = name.strip_suffix('s') {
+        stem.to_string()
+    } else if name.is_empty() {
+        String::new()
+    } else {
+        format!("{}Item", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation<'a>(spec: &str, responses: Vec<(Option<u16>, &'a Value)>) -> ApiOperation<'a> {
+        ApiOperation { responses, ..ApiOperation::parse(spec).unwrap() }
+    }
+
+    #[test]
+    fn operations_parse_with_a_default_method() {
+        let get = ApiOperation::parse("/users/{id}").unwrap();
+        assert_eq!((get.method.as_str(), get.path.as_str()), ("GET", "/users/{id}"));
+        assert_eq!(ApiOperation::parse("post /users").unwrap().method, "POST");
+        assert!(ApiOperation::parse("GET users").is_err());
+        assert!(ApiOperation::parse("GET /a /b").is_err());
+    }
+
+    #[test]
+    fn bodies_list_items_and_shared_objects_become_components() {
+        let list = json!({ "users": [{ "id": 1, "address": { "city": "Oslo" } }] });
+        let one = json!({ "id": 1, "home": { "city": "Rome" } });
+        let missing = json!({ "error": "not found" });
+        let operations = [
+            operation("GET /users", vec![(Some(200), &list)]),
+            operation("GET /users/{id}", vec![(Some(200), &one), (Some(404), &missing)]),
+        ];
+        let document = build_openapi(&operations, "Users", &DistillOptions::default()).unwrap();
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+
+        assert_eq!(
+            schemas.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["GetUsersResponse", "User", "Address", "GetUsersIdResponse", "GetUsersIdResponse404"]
+        );
+        // {city} appears under two parents, so both refer to one component
+        assert_eq!(schemas["User"]["properties"]["address"], json!({ "$ref": "#/components/schemas/Address" }));
+        assert_eq!(schemas["GetUsersIdResponse"]["properties"]["home"], json!({ "$ref": "#/components/schemas/Address" }));
+        assert_eq!(schemas["User"]["required"], json!(["id", "address"]));
+        assert_eq!(schemas["User"]["properties"]["id"], json!({ "type": "integer" }));
+
+        let operation = &document["paths"]["/users/{id}"]["get"];
+        assert_eq!(operation["operationId"], "getUsersId");
+        assert_eq!(operation["parameters"][0]["schema"], json!({ "type": "integer" }));
+        assert_eq!(
+            operation["responses"]["404"]["content"]["application/json"]["schema"],
+            json!({ "$ref": "#/components/schemas/GetUsersIdResponse404" })
+        );
+    }
+
+    #[test]
+    fn alternatives_and_tuples_get_their_own_schemas() {
+        let first = json!({ "points": [[1.5, 2.5], [3.5, 4.5]] });
+        let second = json!({ "points": [] });
+        let operations = [operation("GET /track", vec![(Some(200), &first), (Some(200), &second)])];
+        let options = DistillOptions { detect_tuples: true, ..DistillOptions::default() };
+        let document = build_openapi(&operations, "Tracks", &options).unwrap();
+
+        let schema = &document["paths"]["/track"]["get"]["responses"]["200"]["content"]["application/json"]["schema"];
+        assert_eq!(schema["anyOf"].as_array().unwrap().len(), 2);
+        let schemas = &document["components"]["schemas"];
+        assert_eq!(
+            schemas["GetTrackResponse"]["properties"]["points"]["items"],
+            json!({ "type": "array", "prefixItems": [{ "type": "number" }, { "type": "number" }], "minItems": 2, "maxItems": 2 })
+        );
+        // Same name hint, different structure: told apart by the hash
+        let names: Vec<&String> = schemas.as_object().unwrap().keys().collect();
+        assert_eq!(names.len(), 2);
+        assert_eq!(*names[1], format!("GetTrackResponse_{}", schemas[names[1]]["x-structure-hash"].as_str().unwrap()));
+    }
+
+    #[test]
+    fn names_are_derived_from_keys() {
+        assert_eq!(pascal_case("user_profile-v2"), "UserProfileV2");
+        assert_eq!(singular("Entries"), "Entry");
+        assert_eq!(singular("Address"), "Address");
+        assert_eq!(singular("Data"), "DataItem");
+        assert_eq!(operation_id("DELETE", "/users/{id}/posts"), "deleteUsersIdPosts");
+    }
+}
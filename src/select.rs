@@ -0,0 +1,368 @@
+// src/select.rs - Selecting subtrees (JSON Pointer or a JSONPath subset) before distilling
+
+use crate::core::escape_pointer_token;
+use crate::error::{DistillError, Result};
+use serde_json::{json, Value};
+
+/// Matched paths listed in the "selection" section before eliding the rest
+const SELECTION_PATHS: usize = 10;
+
+/// What a selector matched, reported next to the distilled selection
+#[derive(Debug, Clone)]
+pub struct Selection {
+    pub selector: String,
+    /// JSON Pointers of the matched nodes, in document order
+    pub paths: Vec<String>,
+    /// Whether the selector can only match one node (a JSON Pointer, or a JSONPath without
+    /// wildcards, slices, unions or recursive descent). Other selectors always produce a list.
+    pub definite: bool,
+}
+
+impl Selection {
+    /// Add a "selection" section (before distilled_data) and a description sentence
+    pub fn annotate(&self, distilled: &mut Value) {
+        let Value::Object(map) = distilled else {
+            return;
+        };
+        if let Some(Value::String(description)) = map.get_mut("description") {
+            let path = self.paths.first().map(String::as_str).unwrap_or_default();
+            description.push_str(&match (self.definite, self.paths.len()) {
+                (true, _) if path == self.selector => format!("\nOnly the node at {} was distilled.", path),
+                (true, _) => format!("\nOnly the node at {} ({}) was distilled.", self.selector, path),
+                (false, 1) => format!(
+                    "\nOnly the node matched by {} ({}) was distilled, as a one-item list.",
+                    self.selector, path
+                ),
+                (false, n) => format!(
+                    "\nOnly the {} nodes matched by {} were distilled, as one list (distilled_data is that list).",
+                    n, self.selector
+                ),
+            });
+        }
+        let index = map.keys().position(|k| k == "distilled_data").unwrap_or(map.len());
+        map.shift_insert(index, "selection".to_string(), self.summary());
+    }
+
+    /// Selector, match count and the first matched paths
+    pub fn summary(&self) -> Value {
+        let mut paths: Vec<Value> = self.paths.iter().take(SELECTION_PATHS).map(|p| json!(p)).collect();
+        if self.paths.len() > SELECTION_PATHS {
+            paths.push(json!(format!("... and {} more", self.paths.len() - SELECTION_PATHS)));
+        }
+        json!({
+            "selector": self.selector,
+            "matches": self.paths.len(),
+            "paths": paths,
+        })
+    }
+}
+
+/// Select the part of a document to distill. `selector` is a JSON Pointer (`/data/0/attributes`)
+/// or a JSONPath starting with `$`. Supported JSONPath: `.name`, `['name']`, `[0]`, `[-1]`,
+/// `[*]` / `.*`, slices `[1:5]`, unions `[0,2]` / `['a','b']` and recursive descent `..name`.
+/// A definite selector returns its node; others return the matches as a list (possibly of one).
+/// Matching nothing is an error.
+pub fn select(json_data: &Value, selector: &str) -> Result<(Value, Selection)> {
+    let selector = selector.trim();
+    let (definite, matches) = if selector.is_empty() || selector.starts_with('/') {
+        let node = json_data.pointer(selector).map(|node| (selector.to_string(), node));
+        (true, node.into_iter().collect())
+    } else if selector.starts_with('$') {
+        let segments = parse_json_path(selector)?;
+        let definite = segments.iter().all(Segment::is_definite);
+        (definite, evaluate(json_data, &segments))
+    } else {
+        return Err(DistillError::InvalidInput(format!(
+            "Invalid selector '{}': expected a JSON Pointer (/data/0) or a JSONPath starting with $ ($.data[*])",
+            selector
+        )));
+    };
+    if matches.is_empty() {
+        return Err(DistillError::InvalidInput(format!("Selector '{}' matched nothing", selector)));
+    }
+
+    let paths = matches.iter().map(|(path, _)| path.clone()).collect();
+    let value = if definite {
+        matches[0].1.clone()
+    } else {
+        Value::Array(matches.into_iter().map(|(_, node)| node.clone()).collect())
+    };
+    Ok((value, Selection { selector: selector.to_string(), paths, definite }))
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    Wildcard,
+    Slice(Option<i64>, Option<i64>),
+    Union(Vec<Selector>),
+}
+
+#[derive(Debug)]
+struct Segment {
+    selector: Selector,
+    /// `..`: apply to the node and all its descendants
+    descendant: bool,
+}
+
+impl Segment {
+    fn is_definite(&self) -> bool {
+        !self.descendant && matches!(self.selector, Selector::Name(_) | Selector::Index(_))
+    }
+}
+
+fn parse_json_path(path: &str) -> Result<Vec<Segment>> {
+    let invalid = |reason: &str| DistillError::InvalidInput(format!("Invalid JSONPath '{}': {}", path, reason));
+    let chars: Vec<char> = path.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 1; // after '$'
+
+    while i < chars.len() {
+        let descendant = chars[i] == '.' && chars.get(i + 1) == Some(&'.');
+        let selector = if chars[i] == '.' {
+            i += if descendant { 2 } else { 1 };
+            match chars.get(i) {
+                Some('*') => {
+                    i += 1;
+                    Selector::Wildcard
+                }
+                Some('[') if descendant => continue_bracket(&chars, &mut i).map_err(|e| invalid(&e))?,
+                _ => {
+                    let start = i;
+                    while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                        i += 1;
+                    }
+                    if start == i {
+                        return Err(invalid("expected a name after '.'"));
+                    }
+                    Selector::Name(chars[start..i].iter().collect())
+                }
+            }
+        } else if chars[i] == '[' {
+            continue_bracket(&chars, &mut i).map_err(|e| invalid(&e))?
+        } else {
+            return Err(invalid(&format!("unexpected '{}' at position {}", chars[i], i)));
+        };
+        segments.push(Segment { selector, descendant });
+    }
+    Ok(segments)
+}
+
+/// Parse a `[...]` selector starting at `chars[*i]`, leaving `*i` after the `]`
+fn continue_bracket(chars: &[char], i: &mut usize) -> std::result::Result<Selector, String> {
+    *i += 1;
+    let mut parts: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    loop {
+        let c = *chars.get(*i).ok_or("unclosed '['")?;
+        *i += 1;
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), '\\') => {
+                current.push(*chars.get(*i).ok_or("unclosed string")?);
+                *i += 1;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.push('\u{0}'); // marks a quoted name
+            }
+            (None, ',') => parts.push(std::mem::take(&mut current)),
+            (None, ']') => {
+                parts.push(current);
+                break;
+            }
+            (None, c) if c.is_whitespace() => {}
+            (None, c) => current.push(c),
+        }
+    }
+
+    let mut selectors = parts.iter().map(|part| parse_bracket_part(part)).collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(if selectors.len() == 1 { selectors.remove(0) } else { Selector::Union(selectors) })
+}
+
+fn parse_bracket_part(part: &str) -> std::result::Result<Selector, String> {
+    if let Some(name) = part.strip_prefix('\u{0}') {
+        return Ok(Selector::Name(name.to_string()));
+    }
+    let parse_index = |s: &str| s.parse::<i64>().map_err(|_| format!("invalid index '{}'", s));
+    match part {
+        "*" => Ok(Selector::Wildcard),
+        "" => Err("empty selector".to_string()),
+        p if p.starts_with('?') || p.starts_with('(') => Err("filter and script expressions are not supported".to_string()),
+        p if p.contains(':') => {
+            let mut bounds = p.splitn(3, ':');
+            let start = bounds.next().filter(|s| !s.is_empty()).map(parse_index).transpose()?;
+            let end = bounds.next().filter(|s| !s.is_empty()).map(parse_index).transpose()?;
+            if bounds.next().is_some_and(|step| !step.is_empty() && step != "1") {
+                return Err("slice steps are not supported".to_string());
+            }
+            Ok(Selector::Slice(start, end))
+        }
+        p => parse_index(p).map(Selector::Index),
+    }
+}
+
+/// Nodes matched by a parsed JSONPath, with their JSON Pointers, in document order
+fn evaluate<'a>(root: &'a Value, segments: &[Segment]) -> Vec<(String, &'a Value)> {
+    let mut nodes = vec![(String::new(), root)];
+    for segment in segments {
+        let mut next = Vec::new();
+        for (path, node) in &nodes {
+            if segment.descendant {
+                let mut stack = vec![(path.clone(), *node)];
+                // Depth-first, children in order, so matches come out in document order
+                while let Some((path, node)) = stack.pop() {
+                    apply(&segment.selector, &path, node, &mut next);
+                    let children = children(&path, node);
+                    stack.extend(children.into_iter().rev());
+                }
+            } else {
+                apply(&segment.selector, path, node, &mut next);
+            }
+        }
+        nodes = next;
+    }
+    nodes
+}
+
+fn children<'a>(path: &str, node: &'a Value) -> Vec<(String, &'a Value)> {
+    match node {
+        Value::Object(map) => map.iter().map(|(k, v)| (format!("{}/{}", path, escape_pointer_token(k)), v)).collect(),
+        Value::Array(list) => list.iter().enumerate().map(|(i, v)| (format!("{}/{}", path, i), v)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn apply<'a>(selector: &Selector, path: &str, node: &'a Value, out: &mut Vec<(String, &'a Value)>) {
+    match (selector, node) {
+        (Selector::Name(name), Value::Object(map)) => {
+            if let Some(child) = map.get(name) {
+                out.push((format!("{}/{}", path, escape_pointer_token(name)), child));
+            }
+        }
+        (Selector::Index(index), Value::Array(list)) => {
+            let index = if *index < 0 { list.len() as i64 + index } else { *index };
+            if let Some(child) = usize::try_from(index).ok().and_then(|i| list.get(i)) {
+                out.push((format!("{}/{}", path, index), child));
+            }
+        }
+        (Selector::Wildcard, _) => out.extend(children(path, node)),
+        (Selector::Slice(start, end), Value::Array(list)) => {
+            let len = list.len() as i64;
+            let clamp = |bound: i64| (if bound < 0 { len + bound } else { bound }).clamp(0, len) as usize;
+            let start = start.map_or(0, clamp);
+            let end = end.map_or(list.len(), clamp);
+            for (i, child) in list.iter().enumerate().take(end).skip(start) {
+                out.push((format!("{}/{}", path, i), child));
+            }
+        }
+        (Selector::Union(selectors), _) => {
+            for selector in selectors {
+                apply(selector, path, node, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc() -> Value {
+        json!({
+            "data": [
+                { "id": 1, "a/b": "x", "tags": ["t1"] },
+                { "id": 2, "nested": { "id": 3 } },
+                { "id": 4 }
+            ],
+            "meta": { "id": 5 }
+        })
+    }
+
+    fn paths(selector: &str) -> Vec<String> {
+        select(&doc(), selector).unwrap().1.paths
+    }
+
+    #[test]
+    fn json_pointers_select_one_node() {
+        let (value, selection) = select(&doc(), "/data/1/nested").unwrap();
+        assert_eq!(value, json!({ "id": 3 }));
+        assert!(selection.definite);
+        assert_eq!(select(&doc(), "/data/0/a~1b").unwrap().0, json!("x"));
+        assert_eq!(select(&doc(), "").unwrap().0, doc());
+    }
+
+    #[test]
+    fn definite_json_paths_select_one_node() {
+        let (value, selection) = select(&doc(), "$.data[-1].id").unwrap();
+        assert_eq!(value, json!(4));
+        assert!(selection.definite);
+        assert_eq!(selection.paths, ["/data/2/id"]);
+        assert_eq!(select(&doc(), "$['data'][0]['a/b']").unwrap().1.paths, ["/data/0/a~1b"]);
+        assert_eq!(select(&doc(), "$.data[0][\"tags\"][0]").unwrap().0, json!("t1"));
+    }
+
+    #[test]
+    fn other_json_paths_select_a_list() {
+        let (value, selection) = select(&doc(), "$.data[*].id").unwrap();
+        assert_eq!(value, json!([1, 2, 4]));
+        assert!(!selection.definite);
+        assert_eq!(paths("$.meta.*"), ["/meta/id"]);
+        assert_eq!(select(&doc(), "$.meta.*").unwrap().0, json!([5]));
+
+        assert_eq!(paths("$.data[1:]"), ["/data/1", "/data/2"]);
+        assert_eq!(paths("$.data[:-2]"), ["/data/0"]);
+        assert_eq!(paths("$.data[0:10:1]").len(), 3);
+        assert_eq!(paths("$.data[2,0].id"), ["/data/2/id", "/data/0/id"]);
+        assert_eq!(paths("$['meta','data'][0]"), ["/data/0"]);
+    }
+
+    #[test]
+    fn recursive_descent_matches_in_document_order() {
+        assert_eq!(paths("$..id"), ["/data/0/id", "/data/1/id", "/data/1/nested/id", "/data/2/id", "/meta/id"]);
+        assert_eq!(paths("$.data..[0]"), ["/data/0", "/data/0/tags/0"]);
+    }
+
+    #[test]
+    fn bad_or_empty_selectors_are_errors() {
+        for selector in ["data", "$.", "$.data[", "$.data[?(@.id)]", "$.data[::2]", "$.data[x]", "$.data[]", "$x"] {
+            assert!(matches!(select(&doc(), selector), Err(DistillError::InvalidInput(_))), "{}", selector);
+        }
+        for selector in ["/missing", "$.data[9]", "$.meta[*].x", "$..missing"] {
+            let Err(DistillError::InvalidInput(message)) = select(&doc(), selector) else {
+                panic!("{} matched", selector);
+            };
+            assert!(message.contains("matched nothing"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn annotate_adds_the_selection_before_the_data() {
+        let mut distilled = json!({ "description": "D", "distilled_data": {} });
+        let (_, selection) = select(&doc(), "$..id").unwrap();
+        selection.annotate(&mut distilled);
+        let keys: Vec<&String> = distilled.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["description", "selection", "distilled_data"]);
+        assert_eq!(distilled["selection"]["matches"], 5);
+        let description = distilled["description"].as_str().unwrap();
+        assert!(description.ends_with("Only the 5 nodes matched by $..id were distilled, as one list (distilled_data is that list)."));
+
+        let mut distilled = json!({ "description": "D" });
+        select(&doc(), "$.meta").unwrap().1.annotate(&mut distilled);
+        assert_eq!(distilled["description"], "D\nOnly the node at $.meta (/meta) was distilled.");
+        assert_eq!(distilled["selection"]["paths"], json!(["/meta"]));
+    }
+
+    #[test]
+    fn summary_elides_long_path_lists() {
+        let data = json!((0..12).collect::<Vec<i32>>());
+        let (_, selection) = select(&data, "$[*]").unwrap();
+        let paths = selection.summary()["paths"].as_array().unwrap().clone();
+        assert_eq!(paths.len(), SELECTION_PATHS + 1);
+        assert_eq!(paths[SELECTION_PATHS], "... and 2 more");
+    }
+}
@@ -0,0 +1,125 @@
+// src/session.rs
+
+use dashmap::DashMap;
+use crate::core::DistillOptions;
+use md5::{Digest, Md5};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Maximum number of distilled documents kept for drill-down requests.
+/// Oldest documents are evicted first once the limit is reached.
+const MAX_CACHED_DOCUMENTS: usize = 16;
+
+/// A document distilled during this session, kept so follow-up tools can expand it
+#[derive(Debug)]
+pub struct StoredDocument {
+    pub data: Value,
+    pub distilled: Value,
+    /// Options the document was distilled with, reused for drill-down requests
+    pub options: DistillOptions,
+}
+
+/// Session cache of recently distilled documents, keyed by document id
+#[derive(Clone, Default)]
+pub struct DocumentStore {
+    docs: Arc<DashMap<String, Arc<StoredDocument>>>,
+    // Insertion order for eviction (DashMap has no ordering)
+    order: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl DocumentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derive a stable document id from the raw input and the settings it was distilled
+    /// with, so re-sending the same payload with the same settings returns the same id
+    /// instead of filling the cache with duplicates. Different settings (options, selector)
+    /// get their own entry, so hashes and redaction of earlier responses stay valid
+    pub fn document_id(raw_input: &str, settings: &str) -> String {
+        let mut hasher = Md5::new();
+        hasher.update(raw_input.as_bytes());
+        hasher.update([0]);
+        hasher.update(settings.as_bytes());
+        let digest = hasher.finalize();
+        digest[..6].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Store a document and return its id along with the shared handle
+    pub fn insert(&self, raw_input: &str, settings: &str, document: StoredDocument) -> (String, Arc<StoredDocument>) {
+        let doc_id = Self::document_id(raw_input, settings);
+        let document = Arc::new(document);
+        let mut order = self.order.lock().unwrap_or_else(|e| e.into_inner());
+
+        if self.docs.insert(doc_id.clone(), Arc::clone(&document)).is_some() {
+            // Refresh position of an existing document
+            order.retain(|id| id != &doc_id);
+        }
+        order.push_back(doc_id.clone());
+
+        while order.len() > MAX_CACHED_DOCUMENTS {
+            if let Some(evicted) = order.pop_front() {
+                tracing::debug!("Evicting cached document {}", evicted);
+                self.docs.remove(&evicted);
+            }
+        }
+
+        (doc_id, document)
+    }
+
+    /// Ids of all cached documents, oldest first
+    pub fn ids(&self) -> Vec<String> {
+        let order = self.order.lock().unwrap_or_else(|e| e.into_inner());
+        order.iter().cloned().collect()
+    }
+
+    pub fn get(&self, doc_id: &str) -> Option<Arc<StoredDocument>> {
+        self.docs.get(doc_id).map(|entry| Arc::clone(entry.value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document(strict_typing: bool) -> StoredDocument {
+        StoredDocument {
+            data: json!({"a": 1}),
+            distilled: json!({}),
+            options: DistillOptions { strict_typing, ..DistillOptions::default() },
+        }
+    }
+
+    #[test]
+    fn same_payload_and_settings_share_an_id() {
+        let store = DocumentStore::new();
+        let (first, _) = store.insert("{\"a\": 1}", "strict", document(true));
+        let (second, _) = store.insert("{\"a\": 1}", "strict", document(true));
+        assert_eq!(first, second);
+        assert_eq!(store.ids(), vec![first]);
+    }
+
+    #[test]
+    fn different_settings_keep_earlier_documents() {
+        let store = DocumentStore::new();
+        let (strict, _) = store.insert("{\"a\": 1}", "strict", document(true));
+        let (loose, _) = store.insert("{\"a\": 1}", "loose", document(false));
+        assert_ne!(strict, loose);
+        assert!(store.get(&strict).unwrap().options.strict_typing);
+        assert!(!store.get(&loose).unwrap().options.strict_typing);
+    }
+
+    #[test]
+    fn oldest_documents_are_evicted() {
+        let store = DocumentStore::new();
+        let ids: Vec<String> = (0..MAX_CACHED_DOCUMENTS + 2)
+            .map(|i| store.insert(&i.to_string(), "", document(true)).0)
+            .collect();
+        assert!(store.get(&ids[0]).is_none());
+        assert!(store.get(&ids[1]).is_none());
+        assert!(store.get(&ids[2]).is_some());
+        assert_eq!(store.ids().len(), MAX_CACHED_DOCUMENTS);
+    }
+}
@@ -0,0 +1,517 @@
+// src/structure.rs - Interned structure keys
+
+use crate::error::{DistillError, Result};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use indexmap::IndexMap;
+use md5::{Digest, Md5};
+use rustc_hash::FxHasher;
+use std::fmt;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Digest used for structure hashes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    /// MD5 of the Python repr, matching the Python implementation
+    #[default]
+    Md5,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Hex characters in a full digest
+    fn max_length(self) -> usize {
+        match self {
+            HashAlgorithm::Md5 => 32,
+            HashAlgorithm::Blake3 => 64,
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashAlgorithm::Md5 => f.write_str("md5"),
+            HashAlgorithm::Blake3 => f.write_str("blake3"),
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = DistillError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "md5" => Ok(HashAlgorithm::Md5),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            other => Err(DistillError::InvalidInput(format!(
+                "Unknown hash algorithm '{}' (expected md5 or blake3)",
+                other
+            ))),
+        }
+    }
+}
+
+/// How structure hashes are derived: digest algorithm and number of hex characters kept.
+/// The default (MD5, 8 characters) produces the same hashes as the Python implementation;
+/// longer hashes make collisions between distinct structures unlikely on large datasets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashScheme {
+    pub algorithm: HashAlgorithm,
+    pub length: usize,
+}
+
+impl Default for HashScheme {
+    fn default() -> Self {
+        Self { algorithm: HashAlgorithm::Md5, length: 8 }
+    }
+}
+
+impl HashScheme {
+    pub fn validate(&self) -> Result<()> {
+        let max_length = self.algorithm.max_length();
+        if self.length == 0 || self.length > max_length {
+            return Err(DistillError::InvalidInput(format!(
+                "Hash length for {} must be between 1 and {}, got {}",
+                self.algorithm, max_length, self.length
+            )));
+        }
+        Ok(())
+    }
+
+    /// Truncated hex digest of a structure repr
+    pub(crate) fn digest(&self, repr: &str) -> String {
+        let mut hex = match self.algorithm {
+            HashAlgorithm::Md5 => {
+                // Python: hashlib.md5(repr(key).encode('utf-8')).hexdigest()[:8]
+                let mut hasher = Md5::new();
+                hasher.update(repr.as_bytes());
+                hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect::<String>()
+            }
+            HashAlgorithm::Blake3 => blake3::hash(repr.as_bytes()).to_hex().to_string(),
+        };
+        hex.truncate(self.length);
+        hex
+    }
+}
+
+/// Recorded in output headers as e.g. `md5-8` or `blake3-16`
+impl fmt::Display for HashScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.algorithm, self.length)
+    }
+}
+
+/// One level of a structure tree. Children are interned `StructureKey`s, so building a
+/// node never copies the subtrees below it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum DeepStructureKey {
+    Primitive(&'static str),  // Zero-allocation for common type names
+    Dict(Vec<(String, StructureKey)>),  // Insertion-ordered (matches Python dicts)
+    List(Vec<StructureKey>),            // Sorted vec of unique structures
+    Tuple(Vec<StructureKey>),           // Fixed-length positional array (tuple detection)
+    EmptyList,
+}
+
+/// Interned structure key.
+/// Structurally equal keys share one allocation, so equality and hashing are pointer
+/// operations, and the Python repr and MD5 hash are computed once per distinct structure.
+#[derive(Clone)]
+pub(crate) struct StructureKey(Arc<InternedKey>);
+
+#[derive(Debug)]
+struct InternedKey {
+    node: DeepStructureKey,
+    // Python repr(), used for ordering and hashing
+    repr: String,
+    // Truncated digest of repr (MD5-8 by default, matching Python's structure hashes)
+    hash: String,
+}
+
+impl StructureKey {
+    pub(crate) fn node(&self) -> &DeepStructureKey {
+        &self.0.node
+    }
+
+    /// Python repr() of the key tree
+    pub(crate) fn repr(&self) -> &str {
+        &self.0.repr
+    }
+
+    /// Structure hash shown in distilled output
+    pub(crate) fn hash(&self) -> &str {
+        &self.0.hash
+    }
+
+    /// Human-readable structure signature, e.g. `{id: int, tags: [str]}`.
+    /// Lists show alternatives (`[int | str]`), tuples show positions (`[float, float]`)
+    /// Used in structure catalogs where the Python repr would be too noisy to read
+    pub(crate) fn signature(&self) -> String {
+        match self.node() {
+            DeepStructureKey::Primitive(type_name) => type_name.to_string(),
+            DeepStructureKey::EmptyList => "[]".to_string(),
+            DeepStructureKey::List(elements) => {
+                let element_sigs: Vec<String> = elements.iter().map(|e| e.signature()).collect();
+                format!("[{}]", element_sigs.join(" | "))
+            }
+            DeepStructureKey::Tuple(positions) => {
+                let position_sigs: Vec<String> = positions.iter().map(|p| p.signature()).collect();
+                format!("[{}]", position_sigs.join(", "))
+            }
+            DeepStructureKey::Dict(items) => {
+                let item_sigs: Vec<String> = items
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v.signature()))
+                    .collect();
+                format!("{{{}}}", item_sigs.join(", "))
+            }
+        }
+    }
+
+    /// Signature of the top level only: nested dicts and lists show as `{...}` / `[...]`,
+    /// and at most `max_entries` keys or element types are listed
+    pub(crate) fn short_signature(&self, max_entries: usize) -> String {
+        fn nested(key: &StructureKey) -> String {
+            match key.node() {
+                DeepStructureKey::Primitive(type_name) => type_name.to_string(),
+                DeepStructureKey::EmptyList => "[]".to_string(),
+                DeepStructureKey::Dict(items) if items.is_empty() => "{}".to_string(),
+                DeepStructureKey::Dict(_) => "{...}".to_string(),
+                DeepStructureKey::List(_) | DeepStructureKey::Tuple(_) => "[...]".to_string(),
+            }
+        }
+        fn listed(entries: Vec<String>, max_entries: usize, separator: &str) -> String {
+            let more = entries.len().saturating_sub(max_entries);
+            let mut shown: Vec<String> = entries.into_iter().take(max_entries).collect();
+            if more > 0 {
+                shown.push(format!("... +{} more", more));
+            }
+            shown.join(separator)
+        }
+
+        match self.node() {
+            DeepStructureKey::Primitive(_) | DeepStructureKey::EmptyList => self.signature(),
+            DeepStructureKey::List(elements) => {
+                let kinds: Vec<String> = elements.iter().map(nested).collect();
+                format!("[{}]", listed(kinds, max_entries, " | "))
+            }
+            DeepStructureKey::Tuple(positions) => {
+                let kinds: Vec<String> = positions.iter().map(nested).collect();
+                format!("[{}]", listed(kinds, max_entries, ", "))
+            }
+            DeepStructureKey::Dict(items) => {
+                let fields: Vec<String> = items.iter().map(|(k, v)| format!("{}: {}", k, nested(v))).collect();
+                format!("{{{}}}", listed(fields, max_entries, ", "))
+            }
+        }
+    }
+
+    /// Signature with dict keys sorted at every level, so structures that differ only
+    /// in key order get the same canonical form (used by baseline checks)
+    pub(crate) fn canonical_signature(&self) -> String {
+        match self.node() {
+            DeepStructureKey::Primitive(type_name) => type_name.to_string(),
+            DeepStructureKey::EmptyList => "[]".to_string(),
+            DeepStructureKey::List(elements) => {
+                // Element order follows reprs, which depend on key order; re-sort
+                let mut element_sigs: Vec<String> = elements.iter().map(|e| e.canonical_signature()).collect();
+                element_sigs.sort_unstable();
+                element_sigs.dedup();
+                format!("[{}]", element_sigs.join(" | "))
+            }
+            DeepStructureKey::Tuple(positions) => {
+                let position_sigs: Vec<String> = positions.iter().map(|p| p.canonical_signature()).collect();
+                format!("[{}]", position_sigs.join(", "))
+            }
+            DeepStructureKey::Dict(items) => {
+                let mut item_sigs: Vec<String> = items
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v.canonical_signature()))
+                    .collect();
+                item_sigs.sort_unstable();
+                format!("{{{}}}", item_sigs.join(", "))
+            }
+        }
+    }
+}
+
+// Interning guarantees one allocation per distinct structure within an interner,
+// so pointer identity is structural identity
+impl PartialEq for StructureKey {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for StructureKey {}
+
+impl Hash for StructureKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as usize).hash(state);
+    }
+}
+
+// Implement Ord to match Python's tuple comparison behavior
+// Python sorts by repr() when types can't be compared directly
+impl Ord for StructureKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.repr().cmp(other.repr())
+    }
+}
+
+impl PartialOrd for StructureKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Debug for StructureKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StructureKey({})", self.hash())
+    }
+}
+
+impl DeepStructureKey {
+    /// Convert to Python repr() format for MD5 hashing
+    /// This must match Python's repr() exactly for hash compatibility.
+    /// Children contribute their cached reprs, so this is linear in the node size.
+    fn to_python_repr(&self) -> String {
+        match self {
+            DeepStructureKey::Primitive(type_name) => {
+                format!("('primitive', '{}')", type_name)
+            }
+            DeepStructureKey::EmptyList => {
+                "('list', 'empty')".to_string()
+            }
+            DeepStructureKey::List(elements) => {
+                // Elements are already sorted when DeepStructureKey::List is created
+                // Don't sort again here - that would use string comparison instead of tuple comparison
+                let element_reprs: Vec<&str> = elements.iter().map(|e| e.repr()).collect();
+                // Python requires trailing comma for single-element tuples: (x,) not (x)
+                if element_reprs.len() == 1 {
+                    format!("('list', ({},))", element_reprs[0])
+                } else {
+                    format!("('list', ({}))", element_reprs.join(", "))
+                }
+            }
+            DeepStructureKey::Tuple(positions) => {
+                // Tuples always have 2+ positions, so no trailing comma case
+                let position_reprs: Vec<&str> = positions.iter().map(|p| p.repr()).collect();
+                format!("('tuple', ({}))", position_reprs.join(", "))
+            }
+            DeepStructureKey::Dict(items) => {
+                let items_repr: Vec<String> = items
+                    .iter()
+                    .map(|(k, v)| format!("('{}', {})", k, v.repr()))
+                    .collect();
+                // Python requires trailing comma for single-element tuples: (x,) not (x)
+                if items_repr.len() == 1 {
+                    format!("('dict', ({},))", items_repr[0])
+                } else {
+                    format!("('dict', ({}))", items_repr.join(", "))
+                }
+            }
+        }
+    }
+}
+
+/// Default hasher for interner lookups
+pub(crate) type StructureHasher = BuildHasherDefault<FxHasher>;
+
+/// Interner for structure keys, and the structure cache for a distillation run.
+/// Lookups hash the node and then compare it with `Eq`, so colliding hashes never
+/// resolve to the wrong key. The hasher is a type parameter so collisions can be
+/// forced (e.g. with a constant hasher) when checking that guarantee.
+/// Concurrent so rayon workers hashing list items in parallel share one key space;
+/// keys from different interners must not be compared.
+pub(crate) struct StructureInterner<S = StructureHasher> {
+    keys: DashMap<DeepStructureKey, StructureKey, S>,
+    // Leaves are by far the most common nodes; pre-interned so they skip the map
+    primitives: Vec<StructureKey>,
+    scheme: HashScheme,
+    // First key seen for each short hash, to detect distinct structures sharing a hash
+    hash_owners: DashMap<String, StructureKey, StructureHasher>,
+    collisions: Mutex<Vec<(StructureKey, StructureKey)>>,
+}
+
+/// Primitive type names, as produced by Python's `type(x).__name__` (plus "value"
+/// for non-strict typing)
+const PRIMITIVE_TYPES: [&str; 6] = ["NoneType", "bool", "str", "int", "float", "value"];
+
+impl StructureInterner {
+    pub(crate) fn new(scheme: HashScheme) -> Self {
+        Self::with_hasher(scheme, StructureHasher::default())
+    }
+}
+
+impl<S: BuildHasher + Clone> StructureInterner<S> {
+    pub(crate) fn with_hasher(scheme: HashScheme, hasher: S) -> Self {
+        let mut interner = Self {
+            keys: DashMap::with_hasher(hasher),
+            primitives: Vec::new(),
+            scheme,
+            hash_owners: DashMap::default(),
+            collisions: Mutex::new(Vec::new()),
+        };
+        interner.primitives = PRIMITIVE_TYPES
+            .iter()
+            .map(|type_name| interner.intern(DeepStructureKey::Primitive(type_name)))
+            .collect();
+        interner
+    }
+
+    pub(crate) fn scheme(&self) -> HashScheme {
+        self.scheme
+    }
+
+    /// Number of distinct structures interned
+    pub(crate) fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Shared key for a primitive type name
+    #[inline]
+    pub(crate) fn primitive(&self, type_name: &'static str) -> StructureKey {
+        match PRIMITIVE_TYPES.iter().position(|t| *t == type_name) {
+            Some(index) => self.primitives[index].clone(),
+            None => self.intern(DeepStructureKey::Primitive(type_name)),
+        }
+    }
+
+    /// Return the shared key for `node`, computing its repr and hash on first sight
+    pub(crate) fn intern(&self, node: DeepStructureKey) -> StructureKey {
+        if let Some(existing) = self.keys.get(&node) {
+            return existing.value().clone();
+        }
+
+        let repr = node.to_python_repr();
+        let hash = self.scheme.digest(&repr);
+        match self.keys.entry(node.clone()) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                let key = StructureKey(Arc::new(InternedKey { node, repr, hash }));
+                entry.insert(key.clone());
+                self.record_hash(&key);
+                key
+            }
+        }
+    }
+
+    /// Claim the short hash of a newly interned key, recording a collision if a
+    /// different structure already owns it
+    fn record_hash(&self, key: &StructureKey) {
+        match self.hash_owners.entry(key.hash().to_string()) {
+            Entry::Occupied(owner) => {
+                let mut collisions = self.collisions.lock().unwrap_or_else(|e| e.into_inner());
+                collisions.push((owner.get().clone(), key.clone()));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(key.clone());
+            }
+        }
+    }
+
+    /// Distinct structures that were given the same hash, grouped by hash as
+    /// `[{"hash": ..., "structures": [signature, ...]}]`
+    pub(crate) fn collision_report(&self) -> Vec<serde_json::Value> {
+        let collisions = self.collisions.lock().unwrap_or_else(|e| e.into_inner());
+        let mut by_hash: IndexMap<&str, Vec<String>> = IndexMap::new();
+        for (owner, other) in collisions.iter() {
+            let signatures = by_hash.entry(owner.hash()).or_insert_with(|| vec![owner.signature()]);
+            signatures.push(other.signature());
+        }
+        by_hash
+            .into_iter()
+            .map(|(hash, signatures)| serde_json::json!({ "hash": hash, "structures": signatures }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hashes every node to the same value, so every interner lookup collides
+    #[derive(Clone, Default)]
+    struct ConstantHasher;
+
+    impl Hasher for ConstantHasher {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    impl BuildHasher for ConstantHasher {
+        type Hasher = ConstantHasher;
+
+        fn build_hasher(&self) -> ConstantHasher {
+            ConstantHasher
+        }
+    }
+
+    fn dict<S: BuildHasher + Clone>(interner: &StructureInterner<S>, fields: &[(&str, &'static str)]) -> StructureKey {
+        let items = fields
+            .iter()
+            .map(|(name, type_name)| (name.to_string(), interner.primitive(type_name)))
+            .collect();
+        interner.intern(DeepStructureKey::Dict(items))
+    }
+
+    #[test]
+    fn colliding_lookups_keep_distinct_nodes_apart() {
+        let interner = StructureInterner::with_hasher(HashScheme::default(), ConstantHasher);
+        let by_id = dict(&interner, &[("id", "int")]);
+        let by_name = dict(&interner, &[("name", "str")]);
+        let ints = interner.intern(DeepStructureKey::List(vec![interner.primitive("int")]));
+
+        assert_ne!(by_id, by_name);
+        assert_ne!(by_id, ints);
+        assert_eq!(by_id.signature(), "{id: int}");
+        assert_eq!(by_name.signature(), "{name: str}");
+        assert_eq!(ints.signature(), "[int]");
+
+        // Equal nodes still resolve to the existing key
+        assert_eq!(dict(&interner, &[("id", "int")]), by_id);
+        assert_eq!(interner.intern(DeepStructureKey::List(vec![interner.primitive("int")])), ints);
+        assert_eq!(interner.len(), PRIMITIVE_TYPES.len() + 3);
+    }
+
+    #[test]
+    fn collision_report_lists_structures_sharing_a_hash() {
+        // One hex character leaves 16 possible hashes, so 17 distinct structures must collide
+        let interner = StructureInterner::new(HashScheme { algorithm: HashAlgorithm::Md5, length: 1 });
+        let mut keys: Vec<StructureKey> = (0..17)
+            .map(|i| {
+                let name = format!("field_{}", i);
+                interner.intern(DeepStructureKey::Dict(vec![(name, interner.primitive("int"))]))
+            })
+            .collect();
+        // Pre-interned primitives share the same hash space
+        keys.extend(PRIMITIVE_TYPES.iter().map(|type_name| interner.primitive(type_name)));
+
+        let report = interner.collision_report();
+        assert!(!report.is_empty());
+        for entry in &report {
+            let hash = entry["hash"].as_str().unwrap();
+            let structures = entry["structures"].as_array().unwrap();
+            assert!(structures.len() >= 2);
+            for signature in structures {
+                let key = keys.iter().find(|k| k.signature() == signature.as_str().unwrap()).unwrap();
+                assert_eq!(key.hash(), hash);
+            }
+        }
+    }
+
+    #[test]
+    fn full_length_hashes_report_no_collisions() {
+        let interner = StructureInterner::new(HashScheme { algorithm: HashAlgorithm::Blake3, length: 64 });
+        for i in 0..100 {
+            interner.intern(DeepStructureKey::Dict(vec![(format!("field_{}", i), interner.primitive("str"))]));
+        }
+        assert!(interner.collision_report().is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+// src/timeline.rs - When each structure appeared in an event stream
+
+use crate::core::{self, DistillControl, DistillOptions};
+use crate::error::{DistillError, Result};
+use crate::structure::{StructureInterner, StructureKey};
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Where event timestamps are and how finely they are bucketed
+#[derive(Debug, Clone)]
+pub struct TimelineOptions {
+    /// JSON Pointer (`/meta/ts`) or dotted path (`meta.ts`) of the timestamp in each event
+    pub timestamp_path: String,
+    /// Bucket width in seconds
+    pub bucket_seconds: i64,
+}
+
+impl TimelineOptions {
+    /// Parse a bucket width such as `30s`, `5m`, `1h`, `1d` or `1w`
+    pub fn parse_bucket(spec: &str) -> Result<i64> {
+        let spec = spec.trim();
+        let split = spec.find(|c: char| !c.is_ascii_digit()).unwrap_or(spec.len());
+        let (count, unit) = spec.split_at(split);
+        let count: i64 = if count.is_empty() { 1 } else { count.parse().unwrap_or(0) };
+        let unit_seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86_400,
+            "w" => 7 * 86_400,
+            _ => 0,
+        };
+        if count <= 0 || unit_seconds == 0 {
+            return Err(DistillError::InvalidInput(format!(
+                "Invalid bucket '{}' (expected e.g. 30s, 5m, 1h, 1d or 1w)",
+                spec
+            )));
+        }
+        count
+            .checked_mul(unit_seconds)
+            .ok_or_else(|| DistillError::InvalidInput(format!("Bucket '{}' is too large", spec)))
+    }
+}
+
+/// Per-structure statistics of a timeline
+struct TimelineEntry {
+    signature: String,
+    count: usize,
+    first_seen: Option<i64>,
+    last_seen: Option<i64>,
+    buckets: BTreeMap<i64, usize>,
+}
+
+/// Distill a list of events and add a "timeline" section: for every structure hash (the
+/// events themselves and list items nested in them), its first and last timestamp,
+/// total count and counts per time bucket. Structures are listed by first appearance.
+pub fn distill_timeline(
+    events: Vec<Value>,
+    timeline: &TimelineOptions,
+    options: &DistillOptions,
+    control: &DistillControl,
+) -> Result<Value> {
+    options.hash_scheme.validate()?;
+    let interner = StructureInterner::new(options.hash_scheme);
+    let pointer = to_pointer(&timeline.timestamp_path);
+
+    let mut entries: IndexMap<String, TimelineEntry> = IndexMap::new();
+    let mut without_timestamp = 0;
+    let event_keys = core::item_structure_keys(&events, options, &interner)?;
+    for (event, event_key) in events.iter().zip(&event_keys) {
+        let timestamp = event.pointer(&pointer).and_then(parse_timestamp);
+        if timestamp.is_none() {
+            without_timestamp += 1;
+        }
+        let bucket = timestamp.map(|t| t.div_euclid(timeline.bucket_seconds) * timeline.bucket_seconds);
+        let mut record = |key: &StructureKey| {
+            let entry = entries.entry(key.hash().to_string()).or_insert_with(|| TimelineEntry {
+                signature: key.signature(),
+                count: 0,
+                first_seen: None,
+                last_seen: None,
+                buckets: BTreeMap::new(),
+            });
+            entry.count += 1;
+            if let (Some(t), Some(bucket)) = (timestamp, bucket) {
+                entry.first_seen = Some(entry.first_seen.map_or(t, |first| first.min(t)));
+                entry.last_seen = Some(entry.last_seen.map_or(t, |last| last.max(t)));
+                *entry.buckets.entry(bucket).or_insert(0) += 1;
+            }
+        };
+        // Only containers have structures worth tracking
+        if matches!(event, Value::Object(_) | Value::Array(_)) {
+            record(event_key);
+        }
+        core::visit_nested_item_keys(event, options, &interner, &mut record)?;
+    }
+
+    // Events may be out of order, so sort by first appearance (untimed structures last)
+    entries.sort_by(|_, a, _, b| match (a.first_seen, b.first_seen) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    let structures: Vec<Value> = entries
+        .into_iter()
+        .map(|(hash, entry)| {
+            let buckets: Map<String, Value> = entry
+                .buckets
+                .into_iter()
+                .map(|(bucket, count)| (format_time(bucket), Value::from(count)))
+                .collect();
+            json!({
+                "hash": hash,
+                "signature": entry.signature,
+                "count": entry.count,
+                "first_seen": entry.first_seen.map(format_time),
+                "last_seen": entry.last_seen.map(format_time),
+                "buckets": buckets,
+            })
+        })
+        .collect();
+    let event_count = events.len();
+    let timeline_section = json!({
+        "timestamp_path": pointer,
+        "bucket_seconds": timeline.bucket_seconds,
+        "events": event_count,
+        "events_without_timestamp": without_timestamp,
+        "structures": structures,
+    });
+
+    let mut distilled = core::distill_json_with_options(Value::Array(events), options, control)?;
+    if let Value::Object(map) = &mut distilled {
+        if let Some(Value::String(description)) = map.get_mut("description") {
+            description.push_str(&format!(
+                "\n\"timeline\" lists every structure hash of the {} events (and of lists inside them) \
+with its first_seen/last_seen time (from {}) and counts per {}-second bucket, ordered by first appearance.",
+                event_count, pointer, timeline.bucket_seconds
+            ));
+        }
+        let index = map.keys().position(|k| k == "distilled_data").unwrap_or(map.len());
+        map.shift_insert(index, "timeline".to_string(), timeline_section);
+    }
+    Ok(distilled)
+}
+
+/// `meta.ts` -> `/meta/ts`; JSON Pointers are kept as they are
+fn to_pointer(path: &str) -> String {
+    if path.starts_with('/') || path.is_empty() {
+        return path.to_string();
+    }
+    path.split('.')
+        .map(|segment| format!("/{}", core::escape_pointer_token(segment)))
+        .collect()
+}
+
+/// Unix seconds of a timestamp: RFC 3339 / ISO 8601 text (a date alone is midnight UTC),
+/// or epoch seconds/milliseconds as a number or numeric string (see `epoch_string`).
+/// Typed `{"$date": ...}` wrappers from binary formats are unwrapped.
+fn parse_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_f64().map(epoch_seconds),
+        Value::String(s) => {
+            let s = s.trim();
+            if let Some(n) = epoch_string(s) {
+                return Some(epoch_seconds(n));
+            }
+            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+                return Some(dt.timestamp());
+            }
+            for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+                if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(s, format) {
+                    return Some(dt.and_utc().timestamp());
+                }
+            }
+            chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc().timestamp())
+        }
+        Value::Object(map) if map.len() == 1 => map.get("$date").and_then(parse_timestamp),
+        _ => None,
+    }
+}
+
+/// Numeric strings are epochs only with 9-10 integer digits (seconds, 1973-2286) or 12-13
+/// (milliseconds), so ids such as "42" or "20240131" aren't read as times
+fn epoch_string(s: &str) -> Option<f64> {
+    let integer = s.split_once('.').map_or(s, |(integer, _)| integer);
+    let plausible = matches!(integer.len(), 9 | 10 | 12 | 13) && integer.bytes().all(|b| b.is_ascii_digit());
+    if plausible {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Numbers above 10^11 are taken as milliseconds (10^11 seconds is the year 5138)
+fn epoch_seconds(n: f64) -> i64 {
+    if n.abs() >= 1e11 {
+        (n / 1000.0).floor() as i64
+    } else {
+        n.floor() as i64
+    }
+}
+
+fn format_time(seconds: i64) -> String {
+    chrono::DateTime::from_timestamp(seconds, 0)
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_else(|| seconds.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_widths_parse_with_units() {
+        assert_eq!(TimelineOptions::parse_bucket("30s").unwrap(), 30);
+        assert_eq!(TimelineOptions::parse_bucket(" 5m ").unwrap(), 300);
+        assert_eq!(TimelineOptions::parse_bucket("h").unwrap(), 3600);
+        assert_eq!(TimelineOptions::parse_bucket("2w").unwrap(), 14 * 86_400);
+        for invalid in ["0h", "5", "5y", "-1d", ""] {
+            assert!(TimelineOptions::parse_bucket(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn oversized_buckets_are_rejected_instead_of_overflowing() {
+        let error = TimelineOptions::parse_bucket("9223372036854775807w").unwrap_err();
+        assert!(error.to_string().contains("too large"), "{}", error);
+        assert!(TimelineOptions::parse_bucket("99999999999999999999s").is_err());
+    }
+
+    #[test]
+    fn timestamps_parse_from_text_and_epochs() {
+        assert_eq!(parse_timestamp(&json!("2024-01-31T12:00:00+01:00")), Some(1_706_698_800));
+        assert_eq!(parse_timestamp(&json!("2024-01-31T11:00:00")), Some(1_706_698_800));
+        assert_eq!(parse_timestamp(&json!("2024-01-31")), Some(1_706_659_200));
+        assert_eq!(parse_timestamp(&json!(1_706_698_800)), Some(1_706_698_800));
+        assert_eq!(parse_timestamp(&json!(1_706_698_800_123u64)), Some(1_706_698_800));
+        assert_eq!(parse_timestamp(&json!("1706698800")), Some(1_706_698_800));
+        assert_eq!(parse_timestamp(&json!("1706698800123")), Some(1_706_698_800));
+        assert_eq!(parse_timestamp(&json!("1706698800.5")), Some(1_706_698_800));
+        assert_eq!(parse_timestamp(&json!({ "$date": "2024-01-31" })), Some(1_706_659_200));
+    }
+
+    #[test]
+    fn short_numeric_strings_are_not_epochs() {
+        for id in ["42", "20240131", "12345678901", "-1706698800", "1e9", "not a date"] {
+            assert_eq!(parse_timestamp(&json!(id)), None, "{}", id);
+        }
+    }
+
+    #[test]
+    fn structures_are_ordered_by_first_appearance() {
+        let events = vec![
+            json!({ "ts": "2024-01-02T10:00:00Z", "v": 2, "extra": true }),
+            json!({ "ts": "2024-01-01T10:00:00Z", "v": 1 }),
+            json!({ "ts": "2024-01-01T12:00:00Z", "v": 1 }),
+            json!({ "ts": "42", "v": 1 }),
+        ];
+        let timeline = TimelineOptions { timestamp_path: "ts".to_string(), bucket_seconds: 86_400 };
+        let output = distill_timeline(events, &timeline, &DistillOptions::default(), &DistillControl::default()).unwrap();
+        let section = &output["timeline"];
+        assert_eq!(section["timestamp_path"], "/ts");
+        assert_eq!(section["events_without_timestamp"], 1);
+
+        let structures = section["structures"].as_array().unwrap();
+        assert_eq!(structures[0]["signature"], "{ts: str, v: int}");
+        assert_eq!(structures[0]["count"], 3);
+        assert_eq!(structures[0]["first_seen"], "2024-01-01T10:00:00Z");
+        assert_eq!(structures[0]["last_seen"], "2024-01-01T12:00:00Z");
+        assert_eq!(structures[0]["buckets"], json!({ "2024-01-01T00:00:00Z": 2 }));
+        assert_eq!(structures[1]["signature"], "{ts: str, v: int, extra: bool}");
+    }
+}
@@ -0,0 +1,229 @@
+// src/watch.rs
+
+use crate::batch::InputFile;
+use indexmap::IndexMap;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Changes listed per section of a delta before eliding the rest
+const DELTA_ITEMS: usize = 20;
+
+/// Path, modification time and size of every input. Polling compares fingerprints,
+/// which works on any filesystem (including network mounts where inotify doesn't).
+#[derive(Debug, PartialEq, Eq)]
+pub struct Fingerprint(Vec<(PathBuf, Option<SystemTime>, Option<u64>)>);
+
+impl Fingerprint {
+    pub fn of(inputs: &[InputFile]) -> Self {
+        Fingerprint(
+            inputs
+                .iter()
+                .map(|input| {
+                    let metadata = fs::metadata(&input.path).ok();
+                    (
+                        input.path.clone(),
+                        metadata.as_ref().and_then(|m| m.modified().ok()),
+                        metadata.as_ref().map(|m| m.len()),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Signature and occurrence count of every structure hash, from a structure catalog
+pub type StructureCounts = IndexMap<String, (String, usize)>;
+
+pub fn structure_counts(catalog: &Value) -> StructureCounts {
+    catalog
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|entry| {
+            (
+                entry["hash"].as_str().unwrap_or_default().to_string(),
+                (
+                    entry["signature"].as_str().unwrap_or_default().to_string(),
+                    entry["count"].as_u64().unwrap_or(0) as usize,
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Structural difference between two runs
+#[derive(Debug, Default)]
+pub struct StructureDelta {
+    /// Hashes that weren't there before: (hash, signature, count)
+    pub added: Vec<(String, String, usize)>,
+    /// Hashes that are gone: (hash, signature, previous count)
+    pub removed: Vec<(String, String, usize)>,
+    /// Hashes whose occurrence count changed: (hash, signature, before, after)
+    pub changed: Vec<(String, String, usize, usize)>,
+}
+
+impl StructureDelta {
+    pub fn between(previous: &StructureCounts, current: &StructureCounts) -> Self {
+        let mut delta = StructureDelta::default();
+        for (hash, (signature, count)) in current {
+            match previous.get(hash) {
+                None => delta.added.push((hash.clone(), signature.clone(), *count)),
+                Some((_, before)) if before != count => {
+                    delta.changed.push((hash.clone(), signature.clone(), *before, *count))
+                }
+                Some(_) => {}
+            }
+        }
+        for (hash, (signature, count)) in previous {
+            if !current.contains_key(hash) {
+                delta.removed.push((hash.clone(), signature.clone(), *count));
+            }
+        }
+        delta
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for StructureDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No structural changes");
+        }
+        let lines = self
+            .added
+            .iter()
+            .map(|(hash, signature, count)| format!("+ {} {} (x{})", hash, signature, count))
+            .chain(
+                self.removed
+                    .iter()
+                    .map(|(hash, signature, count)| format!("- {} {} (was x{})", hash, signature, count)),
+            )
+            .chain(self.changed.iter().map(|(hash, signature, before, after)| {
+                format!("~ {} {} (x{} -> x{})", hash, signature, before, after)
+            }));
+        writeln!(
+            f,
+            "{} new, {} removed, {} changed:",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )?;
+        let total = self.added.len() + self.removed.len() + self.changed.len();
+        for line in lines.take(DELTA_ITEMS) {
+            writeln!(f, "  {}", line)?;
+        }
+        if total > DELTA_ITEMS {
+            writeln!(f, "  ... and {} more", total - DELTA_ITEMS)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("json-distiller-watch-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn input(path: &Path) -> InputFile {
+        InputFile { path: path.to_path_buf(), display_name: path.to_path_buf(), output_subdir: PathBuf::new() }
+    }
+
+    fn counts(entries: &[(&str, &str, usize)]) -> StructureCounts {
+        entries
+            .iter()
+            .map(|(hash, signature, count)| (hash.to_string(), (signature.to_string(), *count)))
+            .collect()
+    }
+
+    #[test]
+    fn fingerprints_change_with_the_inputs() {
+        let dir = temp_dir("fingerprint");
+        let path = dir.join("a.json");
+        fs::write(&path, "{}").unwrap();
+        let inputs = [input(&path)];
+
+        let before = Fingerprint::of(&inputs);
+        assert_eq!(Fingerprint::of(&inputs), before);
+        fs::write(&path, r#"{"a": 1}"#).unwrap();
+        let written = Fingerprint::of(&inputs);
+        assert_ne!(written, before);
+        assert_eq!(Fingerprint::of(&inputs), written);
+        assert_ne!(written, Fingerprint::of(&[]));
+
+        // A file that disappears still has an entry, without metadata
+        fs::remove_file(&path).unwrap();
+        assert_ne!(Fingerprint::of(&inputs), written);
+        assert_eq!(Fingerprint::of(&inputs).0[0], (path, None, None));
+    }
+
+    #[test]
+    fn writing_the_output_into_a_watched_directory_is_not_a_change() {
+        let dir = temp_dir("output");
+        fs::write(dir.join("a.json"), r#"{"a": 1}"#).unwrap();
+        let arguments = [dir.clone()];
+        let output = dir.join("report.json");
+        let excluded = [output.clone()];
+
+        let (inputs, _) = batch::expand_inputs(&arguments, &excluded).unwrap();
+        let before = Fingerprint::of(&inputs);
+        for written in [&output, &dir.join("batch_distilled.json"), &dir.join("a_distilled.json")] {
+            fs::write(written, r#"{"distilled_data": {}}"#).unwrap();
+        }
+        let (inputs, _) = batch::expand_inputs(&arguments, &excluded).unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(Fingerprint::of(&inputs), before);
+    }
+
+    #[test]
+    fn structure_counts_come_from_the_catalog() {
+        let catalog = json!([
+            { "hash": "aa", "signature": "{id: int}", "count": 3 },
+            { "hash": "bb", "signature": "[int]", "count": 1 },
+        ]);
+        assert_eq!(structure_counts(&catalog), counts(&[("aa", "{id: int}", 3), ("bb", "[int]", 1)]));
+        assert!(structure_counts(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn deltas_list_added_removed_and_changed_hashes() {
+        let previous = counts(&[("aa", "{id: int}", 3), ("bb", "[int]", 1), ("cc", "{x: str}", 2)]);
+        let current = counts(&[("aa", "{id: int}", 5), ("cc", "{x: str}", 2), ("dd", "{y: bool}", 1)]);
+        let delta = StructureDelta::between(&previous, &current);
+
+        assert_eq!(delta.added, [("dd".to_string(), "{y: bool}".to_string(), 1)]);
+        assert_eq!(delta.removed, [("bb".to_string(), "[int]".to_string(), 1)]);
+        assert_eq!(delta.changed, [("aa".to_string(), "{id: int}".to_string(), 3, 5)]);
+        assert_eq!(
+            delta.to_string(),
+            "1 new, 1 removed, 1 changed:\n  + dd {y: bool} (x1)\n  - bb [int] (was x1)\n  ~ aa {id: int} (x3 -> x5)\n"
+        );
+
+        let unchanged = StructureDelta::between(&current, &current);
+        assert!(unchanged.is_empty());
+        assert_eq!(unchanged.to_string(), "No structural changes\n");
+    }
+
+    #[test]
+    fn long_deltas_are_elided() {
+        let current: StructureCounts =
+            (0..DELTA_ITEMS + 3).map(|i| (format!("h{}", i), ("{}".to_string(), 1))).collect();
+        let text = StructureDelta::between(&StructureCounts::new(), &current).to_string();
+        assert_eq!(text.lines().count(), DELTA_ITEMS + 2);
+        assert!(text.ends_with("  ... and 3 more\n"));
+    }
+}